@@ -0,0 +1,211 @@
+use crate::record_reader::{ends_quoted, Dialect};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::thread;
+
+/// Split the byte range from `start` to the end of the file into at most `count` chunks, each
+/// ending right after a line break (or at the end of the file). Returns `(start, end)` offsets.
+/// With a dialect the line breaks inside quoted fields are skipped, so that every chunk starts
+/// with a record.
+pub fn line_aligned_chunks(
+    path: &Path,
+    start: u64,
+    count: usize,
+    dialect: Option<Dialect>,
+) -> io::Result<Vec<(u64, u64)>> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut boundaries = vec![start];
+    for index in 1..count as u64 {
+        let target = start + (size - start) * index / count as u64;
+        let previous = *boundaries.last().unwrap();
+        if target <= previous {
+            continue;
+        }
+        file.seek(SeekFrom::Start(target - 1))?;
+        let mut skipped = Vec::new();
+        let read = BufReader::new(&file).read_until(b'\n', &mut skipped)?;
+        let boundary = target - 1 + read as u64;
+        if boundary > previous && boundary < size {
+            boundaries.push(boundary);
+        }
+    }
+    boundaries.push(size);
+    if let Some(dialect) = dialect {
+        boundaries = record_boundaries(path, &boundaries, dialect)?;
+    }
+    Ok(boundaries
+        .windows(2)
+        .map(|window| (window[0], window[1]))
+        .collect())
+}
+
+/// Move the line aligned boundaries inside quoted fields to the end of their record. Each chunk
+/// is scanned in parallel both from a record start and from inside a quoted field, the state at
+/// each boundary then follows from the state at the previous one.
+fn record_boundaries(path: &Path, boundaries: &[u64], dialect: Dialect) -> io::Result<Vec<u64>> {
+    let size = *boundaries.last().unwrap();
+    let chunks: Vec<(u64, u64)> = boundaries
+        .windows(2)
+        .map(|window| (window[0], window[1]))
+        .collect();
+    let ends: Vec<io::Result<[bool; 2]>> = thread::scope(|scope| {
+        let handles: Vec<_> = chunks[..chunks.len() - 1]
+            .iter()
+            .map(|&(start, end)| scope.spawn(move || ends_quoted_from(path, start, end, dialect)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+    let mut quoted = false;
+    let mut record_boundaries = vec![boundaries[0]];
+    for (&boundary, ends) in boundaries[1..].iter().zip(ends) {
+        quoted = ends?[quoted as usize];
+        let boundary = if quoted {
+            record_end(path, boundary, true, dialect)?
+        } else {
+            boundary
+        };
+        if boundary > *record_boundaries.last().unwrap() && boundary < size {
+            record_boundaries.push(boundary);
+        }
+    }
+    record_boundaries.push(size);
+    Ok(record_boundaries)
+}
+
+/// Whether the byte range ends inside a quoted field, when starting with a record and when
+/// starting inside a quoted field
+fn ends_quoted_from(path: &Path, start: u64, end: u64, dialect: Dialect) -> io::Result<[bool; 2]> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(start))?;
+    let mut quoted = [false, true];
+    for line in BufReader::new(file.take(end - start)).lines() {
+        let line = line?;
+        quoted = if quoted[0] == quoted[1] {
+            [ends_quoted(&line, quoted[0], dialect); 2]
+        } else {
+            quoted.map(|quoted| ends_quoted(&line, quoted, dialect))
+        };
+    }
+    Ok(quoted)
+}
+
+/// Offset right after the line break ending the record at `start` (or the end of the file),
+/// `quoted` when `start` is inside a quoted field
+pub fn record_end(path: &Path, start: u64, mut quoted: bool, dialect: Dialect) -> io::Result<u64> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(start))?;
+    let mut buf_reader = BufReader::new(file);
+    let mut end = start;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let length = buf_reader.read_line(&mut line)?;
+        end += length as u64;
+        let content = line.strip_suffix('\n').unwrap_or(&line);
+        quoted = ends_quoted(
+            content.strip_suffix('\r').unwrap_or(content),
+            quoted,
+            dialect,
+        );
+        if !quoted || length == 0 {
+            return Ok(end);
+        }
+    }
+}
+
+/// Line number of the first line of each chunk, counting the line breaks before it in parallel
+pub fn first_lines(path: &Path, chunks: &[(u64, u64)]) -> io::Result<Vec<usize>> {
+    let start = chunks.first().map_or(0, |chunk| chunk.0);
+    let line_breaks: Vec<io::Result<usize>> = thread::scope(|scope| {
+        let handles: Vec<_> = [(0, start)]
+            .iter()
+            .chain(chunks)
+            .map(|&(start, end)| scope.spawn(move || count_line_breaks(path, start, end)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+    let mut first_line = 1;
+    let mut first_lines = Vec::new();
+    for line_breaks in line_breaks.into_iter().take(chunks.len()) {
+        first_line += line_breaks?;
+        first_lines.push(first_line);
+    }
+    Ok(first_lines)
+}
+
+fn count_line_breaks(path: &Path, start: u64, end: u64) -> io::Result<usize> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(start))?;
+    let mut buf_reader = BufReader::new(file.take(end - start));
+    let mut count = 0;
+    loop {
+        let buffer = buf_reader.fill_buf()?;
+        if buffer.is_empty() {
+            return Ok(count);
+        }
+        count += buffer.iter().filter(|&&byte| byte == b'\n').count();
+        let length = buffer.len();
+        buf_reader.consume(length);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// Temporary file of the test, unique to the process
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("analyze_data_{}_{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn test_line_aligned_chunks() {
+        let path = temp_path("line_aligned_chunks.txt");
+        fs::write(&path, "aaaa\nbb\ncccccc\nd\n").unwrap();
+
+        let chunks = line_aligned_chunks(&path, 0, 3, None).unwrap();
+        assert_eq!(chunks, vec![(0, 5), (5, 15), (15, 17)]);
+
+        assert_eq!(first_lines(&path, &chunks).unwrap(), vec![1, 2, 4]);
+
+        let chunks = line_aligned_chunks(&path, 5, 2, None).unwrap();
+        assert_eq!(chunks, vec![(5, 15), (15, 17)]);
+        assert_eq!(first_lines(&path, &chunks).unwrap(), vec![2, 4]);
+
+        let chunks = line_aligned_chunks(&path, 0, 100, None).unwrap();
+        assert_eq!(chunks.len(), 4);
+        assert_eq!(chunks.first().unwrap().0, 0);
+        assert_eq!(chunks.last().unwrap().1, 17);
+
+        let chunks = line_aligned_chunks(&path, 17, 4, None).unwrap();
+        assert_eq!(chunks, vec![(17, 17)]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_quoted_line_breaks() {
+        let path = temp_path("quoted_line_breaks.csv");
+        fs::write(&path, "a,\"b\nc\nd\ne\",f\ng,h\n\"i\nj\"\n").unwrap();
+        let dialect = Some(Dialect::default());
+
+        let chunks = line_aligned_chunks(&path, 0, 100, None).unwrap();
+        assert_eq!(chunks.len(), 7);
+        let chunks = line_aligned_chunks(&path, 0, 100, dialect).unwrap();
+        assert_eq!(chunks, vec![(0, 14), (14, 18), (18, 24)]);
+        assert_eq!(first_lines(&path, &chunks).unwrap(), vec![1, 5, 6]);
+
+        assert_eq!(record_end(&path, 0, false, Dialect::default()).unwrap(), 14);
+        assert_eq!(record_end(&path, 7, true, Dialect::default()).unwrap(), 14);
+        fs::remove_file(&path).unwrap();
+    }
+}
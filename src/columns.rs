@@ -0,0 +1,86 @@
+use glob::Pattern;
+
+/// Index of a column referenced by header name or 1-based index
+pub fn column_index(headers: &[String], reference: &str) -> Option<usize> {
+    headers
+        .iter()
+        .position(|header| header == reference)
+        .or_else(|| position(headers, reference))
+}
+
+/// Indices of the columns referenced by header name, 1-based index, range of indices (`2-5`,
+/// `3-` or `-4`) or glob pattern on the header names (`price_*`). None for an unknown reference
+/// or a reversed range.
+pub fn column_indices(headers: &[String], reference: &str) -> Option<Vec<usize>> {
+    if let Some(index) = column_index(headers, reference) {
+        return Some(vec![index]);
+    }
+    if let Some((first, last)) = reference.split_once('-') {
+        let first = match first {
+            "" => Some(0),
+            first => position(headers, first),
+        };
+        let last = match last {
+            "" => headers.len().checked_sub(1),
+            last => position(headers, last),
+        };
+        if let (Some(first), Some(last)) = (first, last) {
+            return (first <= last).then(|| (first..=last).collect());
+        }
+    }
+    if reference.contains(['*', '?', '[']) {
+        let pattern = Pattern::new(reference).ok()?;
+        return Some(
+            (0..headers.len())
+                .filter(|&index| pattern.matches(&headers[index]))
+                .collect(),
+        );
+    }
+    None
+}
+
+/// 0-based position of a 1-based index within the headers
+fn position(headers: &[String], index: &str) -> Option<usize> {
+    index
+        .parse::<usize>()
+        .ok()
+        .filter(|&index| (1..=headers.len()).contains(&index))
+        .map(|index| index - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers() -> Vec<String> {
+        ["id", "name", "price_net", "price-gross", "3"]
+            .iter()
+            .map(|header| header.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_column_index() {
+        let headers = headers();
+        assert_eq!(column_index(&headers, "name"), Some(1));
+        assert_eq!(column_index(&headers, "1"), Some(0));
+        assert_eq!(column_index(&headers, "3"), Some(4));
+        assert_eq!(column_index(&headers, "6"), None);
+        assert_eq!(column_index(&headers, "0"), None);
+    }
+
+    #[test]
+    fn test_column_indices() {
+        let headers = headers();
+        assert_eq!(column_indices(&headers, "price-gross"), Some(vec![3]));
+        assert_eq!(column_indices(&headers, "2-4"), Some(vec![1, 2, 3]));
+        assert_eq!(column_indices(&headers, "4-"), Some(vec![3, 4]));
+        assert_eq!(column_indices(&headers, "-2"), Some(vec![0, 1]));
+        assert_eq!(column_indices(&headers, "price*"), Some(vec![2, 3]));
+        assert_eq!(column_indices(&headers, "cost*"), Some(vec![]));
+        assert_eq!(column_indices(&headers, "cost"), None);
+        assert_eq!(column_indices(&headers, "2-9"), None);
+        assert_eq!(column_indices(&headers, "4-2"), None);
+        assert_eq!(column_indices(&headers, "3-3"), Some(vec![2]));
+    }
+}
@@ -0,0 +1,171 @@
+use crate::value_sample::ValueSample;
+
+/// Number of rows sampled for the Spearman correlations, which are exact up to this many rows
+const SPEARMAN_SAMPLE_SIZE: usize = 10_000;
+
+/// Co-moments of the rows where both columns of a pair are numbers
+#[derive(Copy, Clone, Debug, Default)]
+struct CoMoments {
+    count: usize,
+    mean_x: f64,
+    mean_y: f64,
+    /// Sums of the squared deviations and of the products of the deviations
+    m2_x: f64,
+    m2_y: f64,
+    c: f64,
+}
+
+impl CoMoments {
+    fn add(&mut self, x: f64, y: f64) {
+        self.count += 1;
+        let count = self.count as f64;
+        let (dx, dy) = (x - self.mean_x, y - self.mean_y);
+        self.mean_x += dx / count;
+        self.mean_y += dy / count;
+        self.m2_x += dx * (x - self.mean_x);
+        self.m2_y += dy * (y - self.mean_y);
+        self.c += dx * (y - self.mean_y);
+    }
+    fn merge(&mut self, other: &Self) {
+        if other.count == 0 {
+            return;
+        }
+        let (count_a, count_b) = (self.count as f64, other.count as f64);
+        let count = count_a + count_b;
+        let (dx, dy) = (other.mean_x - self.mean_x, other.mean_y - self.mean_y);
+        self.mean_x += dx * count_b / count;
+        self.mean_y += dy * count_b / count;
+        self.m2_x += other.m2_x + dx * dx * count_a * count_b / count;
+        self.m2_y += other.m2_y + dy * dy * count_a * count_b / count;
+        self.c += other.c + dx * dy * count_a * count_b / count;
+        self.count += other.count;
+    }
+    fn pearson(&self) -> Option<f64> {
+        let denominator = (self.m2_x * self.m2_y).sqrt();
+        (self.count >= 2 && denominator > 0.0).then(|| self.c / denominator)
+    }
+}
+
+/// Pearson correlations of every pair of columns, streaming over the rows where both values are
+/// numbers, and Spearman correlations on a uniform sample of the rows
+#[derive(Clone, Debug)]
+pub struct CorrelationStats {
+    columns: usize,
+    /// Pairs `(i, j)` with `i < j`, row by row
+    pairs: Vec<CoMoments>,
+    /// Rows with NaN for the values that are not numbers
+    sample: ValueSample<Vec<f64>>,
+}
+
+impl CorrelationStats {
+    pub fn new(columns: usize) -> Self {
+        Self {
+            columns,
+            pairs: vec![CoMoments::default(); columns * columns.saturating_sub(1) / 2],
+            sample: ValueSample::uniform(SPEARMAN_SAMPLE_SIZE),
+        }
+    }
+    /// Add a row with the numbers of the columns
+    pub fn add(&mut self, row: &[Option<f64>]) {
+        let mut pairs = self.pairs.iter_mut();
+        for (i, x) in row.iter().enumerate() {
+            for y in &row[i + 1..] {
+                let co_moments = pairs.next().unwrap();
+                if let (Some(x), Some(y)) = (x, y) {
+                    co_moments.add(*x, *y);
+                }
+            }
+        }
+        self.sample
+            .add(row.iter().map(|num| num.unwrap_or(f64::NAN)).collect());
+    }
+    /// Combine with stats of another part of the input, as if all rows were added to this one
+    pub fn merge(&mut self, other: &Self) {
+        for (co_moments, other_co_moments) in self.pairs.iter_mut().zip(&other.pairs) {
+            co_moments.merge(other_co_moments);
+        }
+        self.sample.merge(&other.sample);
+    }
+    /// Number of rows where both columns are numbers
+    pub fn count(&self, i: usize, j: usize) -> usize {
+        self.co_moments(i, j).count
+    }
+    pub fn pearson(&self, i: usize, j: usize) -> Option<f64> {
+        self.co_moments(i, j).pearson()
+    }
+    /// Pearson correlation of the ranks of the sampled rows where both columns are numbers
+    pub fn spearman(&self, i: usize, j: usize) -> Option<f64> {
+        let (x, y): (Vec<f64>, Vec<f64>) = self
+            .sample
+            .values()
+            .into_iter()
+            .map(|row| (row[i], row[j]))
+            .filter(|(x, y)| !x.is_nan() && !y.is_nan())
+            .unzip();
+        let mut co_moments = CoMoments::default();
+        for (x, y) in ranks(&x).into_iter().zip(ranks(&y)) {
+            co_moments.add(x, y);
+        }
+        co_moments.pearson()
+    }
+
+    fn co_moments(&self, i: usize, j: usize) -> &CoMoments {
+        let (i, j) = (i.min(j), i.max(j));
+        // Pairs of the previous rows, then the offset in row i
+        &self.pairs[i * (2 * self.columns - i - 1) / 2 + (j - i - 1)]
+    }
+}
+
+/// 1-based ranks of the values, the average rank for ties
+fn ranks(values: &[f64]) -> Vec<f64> {
+    let mut indices: Vec<usize> = (0..values.len()).collect();
+    indices.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < indices.len() {
+        let end = indices[start..]
+            .iter()
+            .position(|&index| values[index] != values[indices[start]])
+            .map_or(indices.len(), |length| start + length);
+        let rank = (start + end + 1) as f64 / 2.0;
+        for &index in &indices[start..end] {
+            ranks[index] = rank;
+        }
+        start = end;
+    }
+    ranks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ranks() {
+        assert_eq!(ranks(&[10.0, 30.0, 20.0, 20.0]), [1.0, 4.0, 2.5, 2.5]);
+    }
+
+    #[test]
+    fn test_correlations() {
+        let (mut stats, mut other) = (CorrelationStats::new(3), CorrelationStats::new(3));
+        for value in 1..=10 {
+            let x = value as f64;
+            let row = [Some(x), Some(x * x), Some(-x)];
+            if value % 2 == 0 {
+                stats.add(&row);
+            } else {
+                other.add(&row);
+            }
+        }
+        stats.add(&[Some(1.0), None, None]);
+        stats.merge(&other);
+        assert_eq!(stats.count(0, 1), 10);
+        assert_eq!(stats.count(1, 0), 10);
+        assert!((stats.pearson(0, 2).unwrap() + 1.0).abs() < 1e-9);
+        let pearson = stats.pearson(0, 1).unwrap();
+        assert!(pearson > 0.9 && pearson < 1.0);
+        assert!((stats.spearman(0, 1).unwrap() - 1.0).abs() < 1e-9);
+        assert!((stats.spearman(1, 2).unwrap() + 1.0).abs() < 1e-9);
+        assert_eq!(CorrelationStats::new(2).pearson(0, 1), None);
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+/// Exact number of occurrences of each distinct value
+#[derive(Clone, Debug, Default)]
+pub struct CountStats {
+    counts: HashMap<String, usize>,
+}
+
+impl CountStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn add(&mut self, value: String) {
+        *self.counts.entry(value).or_default() += 1;
+    }
+    /// Combine with stats of another part of the input, as if all values were added to this one
+    pub fn merge(&mut self, other: &Self) {
+        for (value, &count) in &other.counts {
+            match self.counts.get_mut(value) {
+                Some(self_count) => *self_count += count,
+                None => {
+                    self.counts.insert(value.clone(), count);
+                }
+            }
+        }
+    }
+    /// Number of values
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+    /// Number of distinct values
+    pub fn distinct(&self) -> usize {
+        self.counts.len()
+    }
+    /// Distinct values with their count, most frequent first (then by value)
+    pub fn counts(&self) -> Vec<(String, usize)> {
+        let mut counts: Vec<(String, usize)> = self
+            .counts
+            .iter()
+            .map(|(value, &count)| (value.clone(), count))
+            .collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts() {
+        let mut stats = CountStats::new();
+        for value in ["b", "a", "b", ""] {
+            stats.add(value.to_string());
+        }
+        let mut other = CountStats::new();
+        other.add("a".to_string());
+        other.add("c".to_string());
+        stats.merge(&other);
+        assert_eq!(stats.total(), 6);
+        assert_eq!(stats.distinct(), 4);
+        assert_eq!(
+            stats.counts(),
+            [
+                ("a".to_string(), 2),
+                ("b".to_string(), 2),
+                ("".to_string(), 1),
+                ("c".to_string(), 1)
+            ]
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use std::collections::HashMap;
+
+/// Epoch values from this magnitude on are milliseconds (as seconds they would be after year 5000)
+const EPOCH_MILLIS_FROM: i64 = 100_000_000_000;
+
+/// Recognizes dates and datetimes in the given `strftime` formats, then as RFC 3339, ISO 8601
+/// and optionally epoch seconds or milliseconds
+#[derive(Clone, Debug, Default)]
+pub struct DateParser {
+    formats: Vec<String>,
+    epoch: bool,
+}
+
+impl DateParser {
+    pub fn new(formats: &[String], epoch: bool) -> Self {
+        Self {
+            formats: formats.to_vec(),
+            epoch,
+        }
+    }
+
+    /// Whether numbers can be dates, as epoch seconds or millis or in the given formats
+    pub fn parses_numbers(&self) -> bool {
+        self.epoch || !self.formats.is_empty()
+    }
+
+    /// Datetime (in UTC for values with an offset) and name of the format the value is in
+    pub fn parse<'a>(&'a self, value: &str) -> Option<(NaiveDateTime, &'a str)> {
+        for format in &self.formats {
+            if let Some(datetime) = parse_with_format(value, format) {
+                return Some((datetime, format));
+            }
+        }
+        // The ISO 8601 and RFC 3339 formats start with a (signed) year and have dashes, don't
+        // try them on the other values
+        let starts_with_year = value
+            .trim_start()
+            .starts_with(|c: char| c.is_ascii_digit() || c == '+' || c == '-');
+        if starts_with_year && value.contains('-') {
+            // Dates first, as the most common, no datetime is also a date
+            if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+                return Some((date.and_time(Default::default()), "iso8601-date"));
+            }
+            if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+                return Some((datetime.naive_utc(), "rfc3339"));
+            }
+            if let Ok(datetime) = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f")
+                .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f"))
+            {
+                return Some((datetime, "iso8601-datetime"));
+            }
+        }
+        if self.epoch {
+            let epoch = value.parse::<i64>().ok()?;
+            return if epoch.abs() >= EPOCH_MILLIS_FROM {
+                DateTime::from_timestamp_millis(epoch)
+                    .map(|datetime| (datetime.naive_utc(), "epoch-millis"))
+            } else {
+                DateTime::from_timestamp(epoch, 0)
+                    .map(|datetime| (datetime.naive_utc(), "epoch-seconds"))
+            };
+        }
+        None
+    }
+}
+
+/// A datetime, a datetime with offset or a date in the `strftime` format
+fn parse_with_format(value: &str, format: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value, format)
+        .ok()
+        .or_else(|| {
+            DateTime::parse_from_str(value, format)
+                .ok()
+                .map(|datetime| datetime.naive_utc())
+        })
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, format)
+                .ok()
+                .map(|date| date.and_time(Default::default()))
+        })
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DateStats {
+    count: usize,
+    empty_count: usize,
+    error_count: usize,
+    min: Option<NaiveDateTime>,
+    max: Option<NaiveDateTime>,
+    format_counts: HashMap<String, usize>,
+}
+
+impl DateStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Add a datetime in the format named `format`
+    pub fn add(&mut self, datetime: NaiveDateTime, format: &str) {
+        self.count += 1;
+        self.min = Some(self.min.map_or(datetime, |min| min.min(datetime)));
+        self.max = Some(self.max.map_or(datetime, |max| max.max(datetime)));
+        match self.format_counts.get_mut(format) {
+            Some(count) => *count += 1,
+            None => {
+                self.format_counts.insert(format.to_string(), 1);
+            }
+        }
+    }
+    /// Combine with stats of another part of the input, as if all values were added to this one
+    pub fn merge(&mut self, other: &Self) {
+        self.count += other.count;
+        self.empty_count += other.empty_count;
+        self.error_count += other.error_count;
+        self.min = self.min.into_iter().chain(other.min).min();
+        self.max = self.max.into_iter().chain(other.max).max();
+        for (format, count) in &other.format_counts {
+            *self.format_counts.entry(format.clone()).or_default() += count;
+        }
+    }
+    pub fn add_empty(&mut self) {
+        self.empty_count += 1;
+    }
+    pub fn add_error(&mut self) {
+        self.error_count += 1;
+    }
+    pub fn count(&self) -> usize {
+        self.count
+    }
+    pub fn empty_count(&self) -> usize {
+        self.empty_count
+    }
+    pub fn error_count(&self) -> usize {
+        self.error_count
+    }
+    pub fn min(&self) -> Option<NaiveDateTime> {
+        self.min
+    }
+    pub fn max(&self) -> Option<NaiveDateTime> {
+        self.max
+    }
+    /// Time between the min and the max in seconds
+    pub fn span(&self) -> Option<f64> {
+        let span = self.max? - self.min?;
+        Some(span.num_milliseconds() as f64 / 1000.0)
+    }
+    /// Formats with the number of values in them, most frequent first
+    pub fn format_counts(&self) -> Vec<(String, usize)> {
+        let mut format_counts: Vec<(String, usize)> = self
+            .format_counts
+            .iter()
+            .map(|(format, &count)| (format.clone(), count))
+            .collect();
+        format_counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        format_counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn datetime(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let parser = DateParser::new(&["%d/%m/%Y".to_string()], true);
+        assert_eq!(
+            parser.parse("2024-03-01T10:00:00+02:00"),
+            Some((datetime("2024-03-01 08:00:00"), "rfc3339"))
+        );
+        assert_eq!(
+            parser.parse("2024-03-01 10:00:00"),
+            Some((datetime("2024-03-01 10:00:00"), "iso8601-datetime"))
+        );
+        assert_eq!(
+            parser.parse("2024-03-01"),
+            Some((datetime("2024-03-01 00:00:00"), "iso8601-date"))
+        );
+        assert_eq!(
+            parser.parse("01/03/2024"),
+            Some((datetime("2024-03-01 00:00:00"), "%d/%m/%Y"))
+        );
+        assert_eq!(
+            parser.parse("1709287200"),
+            Some((datetime("2024-03-01 10:00:00"), "epoch-seconds"))
+        );
+        assert_eq!(
+            parser.parse("1709287200000"),
+            Some((datetime("2024-03-01 10:00:00"), "epoch-millis"))
+        );
+        assert_eq!(parser.parse("x"), None);
+        assert_eq!(parser.parse("n-1"), None);
+        assert_eq!(
+            parser.parse(" 2024-03-01").map(|(_, format)| format),
+            Some("iso8601-date")
+        );
+        assert_eq!(DateParser::new(&[], false).parse("1709287200"), None);
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut stats = DateStats::new();
+        assert_eq!(stats.span(), None);
+        stats.add(datetime("2024-03-02 00:00:00"), "iso8601-date");
+        stats.add(datetime("2024-03-01 12:00:00"), "rfc3339");
+        stats.add_empty();
+        let mut other = DateStats::new();
+        other.add(datetime("2024-03-03 00:00:00"), "iso8601-date");
+        other.add_error();
+        stats.merge(&other);
+        assert_eq!(stats.count(), 3);
+        assert_eq!(stats.empty_count(), 1);
+        assert_eq!(stats.error_count(), 1);
+        assert_eq!(stats.min(), Some(datetime("2024-03-01 12:00:00")));
+        assert_eq!(stats.max(), Some(datetime("2024-03-03 00:00:00")));
+        assert_eq!(stats.span(), Some(129600.0));
+        assert_eq!(
+            stats.format_counts(),
+            [("iso8601-date".to_string(), 2), ("rfc3339".to_string(), 1)]
+        );
+    }
+}
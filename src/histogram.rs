@@ -0,0 +1,258 @@
+use crate::t_digest::TDigest;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// How the histogram bins are laid out
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum HistogramBins {
+    /// The given number of equal width bins between the min and the max, with approximate counts
+    /// from the quantile sketch
+    Auto(usize),
+    /// The given number of equal width bins between a min and a max, plus a bin below and one
+    /// above them when needed
+    Fixed { min: f64, max: f64, count: usize },
+    /// The given number of bins per power of 10 for the positive values, plus a bin for zero and
+    /// the negative values when needed
+    Log(usize),
+}
+
+impl FromStr for HistogramBins {
+    type Err = String;
+
+    /// Parse `auto:<count>`, `fixed:<min>,<max>,<count>` or `log:<bins per decade>`
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let error = || {
+            format!(
+                "{} is not like auto:<count>, fixed:<min>,<max>,<count> or log:<bins per decade>",
+                value
+            )
+        };
+        let count = |count: &str| match count.parse::<usize>() {
+            Ok(count) if count > 0 => Ok(count),
+            _ => Err(error()),
+        };
+        match value.split_once(':').ok_or_else(error)? {
+            ("auto", bins) => Ok(Self::Auto(count(bins)?)),
+            ("log", bins) => Ok(Self::Log(count(bins)?)),
+            ("fixed", bins) => {
+                let bins: Vec<&str> = bins.split(',').collect();
+                let [min, max, bins] = bins[..] else {
+                    return Err(error());
+                };
+                let (min, max) = match (min.parse::<f64>(), max.parse::<f64>()) {
+                    (Ok(min), Ok(max)) if min < max => (min, max),
+                    _ => return Err(error()),
+                };
+                Ok(Self::Fixed {
+                    min,
+                    max,
+                    count: count(bins)?,
+                })
+            }
+            _ => Err(error()),
+        }
+    }
+}
+
+/// Exact bin counts for fixed and log bins (the auto bins come from the quantile sketch)
+#[derive(Clone, Debug)]
+pub struct Histogram {
+    bins: HistogramBins,
+    counts: BTreeMap<i64, usize>,
+}
+
+/// Bin keys of the values below and above the fixed bins, and of the non-positive values of the
+/// log bins
+const BELOW: i64 = i64::MIN;
+const ABOVE: i64 = i64::MAX;
+
+impl Histogram {
+    pub fn new(bins: HistogramBins) -> Self {
+        Self {
+            bins,
+            counts: BTreeMap::new(),
+        }
+    }
+    pub fn add(&mut self, num: f64) {
+        let key = match self.bins {
+            _ if num.is_nan() => return,
+            HistogramBins::Auto(_) => return,
+            HistogramBins::Fixed { min, max, count } => {
+                if num < min {
+                    BELOW
+                } else if num > max {
+                    ABOVE
+                } else {
+                    // The max is in the last bin
+                    (((num - min) / (max - min) * count as f64) as i64).min(count as i64 - 1)
+                }
+            }
+            HistogramBins::Log(per_decade) => {
+                if num <= 0.0 {
+                    BELOW
+                } else {
+                    (num.log10() * per_decade as f64).floor() as i64
+                }
+            }
+        };
+        *self.counts.entry(key).or_default() += 1;
+    }
+    /// Combine with a histogram of another part of the input, as if all values were added to
+    /// this one
+    pub fn merge(&mut self, other: &Self) {
+        for (&key, &count) in &other.counts {
+            *self.counts.entry(key).or_default() += count;
+        }
+    }
+    /// Lower bound (None for -inf), upper bound (None for inf) and count of the bins in order,
+    /// the last bound of the in-range bins is inclusive. The auto bins are estimated from the
+    /// quantile sketch.
+    pub fn bins(&self, t_digest: &TDigest) -> Vec<(Option<f64>, Option<f64>, usize)> {
+        match self.bins {
+            HistogramBins::Auto(count) => auto_bins(t_digest, count),
+            HistogramBins::Fixed { min, max, count } => {
+                let width = (max - min) / count as f64;
+                let mut bins = Vec::new();
+                if let Some(&below) = self.counts.get(&BELOW) {
+                    bins.push((None, Some(min), below));
+                }
+                bins.extend((0..count).map(|index| {
+                    (
+                        Some(min + width * index as f64),
+                        Some(min + width * (index + 1) as f64),
+                        self.counts.get(&(index as i64)).copied().unwrap_or(0),
+                    )
+                }));
+                if let Some(&above) = self.counts.get(&ABOVE) {
+                    bins.push((Some(max), None, above));
+                }
+                bins
+            }
+            HistogramBins::Log(per_decade) => {
+                let bound = |key: i64| 10f64.powf(key as f64 / per_decade as f64);
+                let mut bins = Vec::new();
+                if let Some(&non_positive) = self.counts.get(&BELOW) {
+                    bins.push((None, Some(0.0), non_positive));
+                }
+                if let (Some(&first), Some(&last)) = (
+                    self.counts.keys().find(|&&key| key != BELOW),
+                    self.counts.keys().next_back(),
+                ) {
+                    bins.extend((first..=last).map(|key| {
+                        (
+                            Some(bound(key)),
+                            Some(bound(key + 1)),
+                            self.counts.get(&key).copied().unwrap_or(0),
+                        )
+                    }));
+                }
+                bins
+            }
+        }
+    }
+}
+
+/// Equal width bins between the min and the max with the counts estimated from the sketch
+fn auto_bins(t_digest: &TDigest, count: usize) -> Vec<(Option<f64>, Option<f64>, usize)> {
+    let (Some(min), Some(max)) = (t_digest.quantile(0.0), t_digest.quantile(1.0)) else {
+        return Vec::new();
+    };
+    let total = t_digest.count().round() as usize;
+    if min == max {
+        return vec![(Some(min), Some(max), total)];
+    }
+    let width = (max - min) / count as f64;
+    let rank = |index: usize| -> usize {
+        if index == count {
+            total
+        } else {
+            t_digest.rank(min + width * index as f64).round() as usize
+        }
+    };
+    (0..count)
+        .map(|index| {
+            (
+                Some(min + width * index as f64),
+                Some(min + width * (index + 1) as f64),
+                rank(index + 1) - rank(index),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn histogram(bins: HistogramBins, values: &[f64]) -> (Histogram, TDigest) {
+        let (mut histogram, mut t_digest) = (Histogram::new(bins), TDigest::new());
+        for &value in values {
+            histogram.add(value);
+            t_digest.add(value);
+        }
+        (histogram, t_digest)
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("auto:10".parse(), Ok(HistogramBins::Auto(10)));
+        assert_eq!("log:2".parse(), Ok(HistogramBins::Log(2)));
+        assert_eq!(
+            "fixed:0,100,4".parse(),
+            Ok(HistogramBins::Fixed {
+                min: 0.0,
+                max: 100.0,
+                count: 4
+            })
+        );
+        assert!("fixed:100,0,4".parse::<HistogramBins>().is_err());
+        assert!("auto:0".parse::<HistogramBins>().is_err());
+        assert!("10".parse::<HistogramBins>().is_err());
+    }
+
+    #[test]
+    fn test_auto() {
+        let (histogram, t_digest) = histogram(HistogramBins::Auto(2), &[1.0, 2.0, 2.0, 3.0, 5.0]);
+        assert_eq!(
+            histogram.bins(&t_digest),
+            [(Some(1.0), Some(3.0), 3), (Some(3.0), Some(5.0), 2)]
+        );
+        assert!(histogram.bins(&TDigest::new()).is_empty());
+    }
+
+    #[test]
+    fn test_fixed() {
+        let bins = HistogramBins::Fixed {
+            min: 0.0,
+            max: 10.0,
+            count: 2,
+        };
+        let (mut histogram, t_digest) = histogram(bins, &[0.0, 4.0, 5.0, 10.0]);
+        let (other, _) = self::histogram(bins, &[-1.0, 11.0, 12.0]);
+        histogram.merge(&other);
+        assert_eq!(
+            histogram.bins(&t_digest),
+            [
+                (None, Some(0.0), 1),
+                (Some(0.0), Some(5.0), 2),
+                (Some(5.0), Some(10.0), 2),
+                (Some(10.0), None, 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_log() {
+        let (histogram, t_digest) =
+            histogram(HistogramBins::Log(1), &[0.0, 1.0, 5.0, 150.0, 999.0]);
+        assert_eq!(
+            histogram.bins(&t_digest),
+            [
+                (None, Some(0.0), 1),
+                (Some(1.0), Some(10.0), 2),
+                (Some(10.0), Some(100.0), 0),
+                (Some(100.0), Some(1000.0), 2)
+            ]
+        );
+    }
+}
@@ -0,0 +1,54 @@
+//! Streaming stats engine behind the analyze_data CLI.
+//!
+//! Build a [`Profiler`] for a [`Mode`], feed it a stream or parsed records and get a structured,
+//! serializable [`Profile`] back. The [`NumberStats`], [`StringStats`] and [`DateStats`]
+//! accumulators can also be used on their own.
+pub(crate) mod chunk;
+pub(crate) mod columns;
+pub(crate) mod correlation_stats;
+pub(crate) mod count_stats;
+pub(crate) mod date_stats;
+pub(crate) mod histogram;
+pub(crate) mod mode;
+pub(crate) mod moments;
+pub(crate) mod number_bin;
+pub(crate) mod number_format;
+pub(crate) mod number_stats;
+pub(crate) mod output_format;
+pub(crate) mod profile;
+pub(crate) mod profiler;
+pub(crate) mod ragged_stats;
+pub(crate) mod record_reader;
+pub(crate) mod reject;
+pub(crate) mod render;
+pub(crate) mod report;
+pub(crate) mod space_saving;
+pub(crate) mod stat;
+pub(crate) mod string_stats;
+pub(crate) mod t_digest;
+pub(crate) mod time_bucket;
+pub(crate) mod type_stats;
+pub(crate) mod value_sample;
+
+pub use correlation_stats::CorrelationStats;
+pub use count_stats::CountStats;
+pub use date_stats::{DateParser, DateStats};
+pub use histogram::HistogramBins;
+pub use mode::{print, Mode};
+pub use number_bin::{Binning, NumberBin};
+pub use number_format::NumberFormat;
+pub use number_stats::NumberStats;
+pub use output_format::OutputFormat;
+pub use profile::{
+    Correlation, CountSummary, DateSummary, FormatCount, HistogramBin, MeanInterval, NumberSummary,
+    Percentile, Profile, ProfileRow, RaggedSummary, StringSummary, TopValue, TypeSummary,
+    ValueCount,
+};
+pub use profiler::Profiler;
+pub use record_reader::Dialect;
+pub use reject::{write_rejects, Reject, RejectReason};
+pub use report::{Report, ReportRow, Section, StatValue};
+pub use stat::Stat;
+pub use string_stats::StringStats;
+pub use time_bucket::{TimeBucket, TimeUnit};
+pub use type_stats::{TypeStats, ValueType};
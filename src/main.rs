@@ -1,50 +1,171 @@
-mod mode;
-mod number_stats;
-mod string_stats;
-
-use clap::{CommandFactory, Parser, ValueEnum};
+use analyze_data::{
+    print, write_rejects, Dialect, HistogramBins, Mode, NumberBin, NumberFormat, OutputFormat,
+    Profiler, Stat, TimeBucket,
+};
+use clap::{error::ErrorKind, CommandFactory, Parser, ValueEnum};
 use is_terminal::IsTerminal as _;
 use std::{
     fs::File,
-    io::{stdin, BufRead, BufReader},
-    path::PathBuf,
+    io::{self, stdin, BufReader, BufWriter},
+    path::{Path, PathBuf},
 };
 
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
-enum Mode {
-    /// Run stats on input as number
-    Number,
-    /// Run stats on input as string
-    String,
-    /// Run stats on last column as number and interpret preceding columns as group
-    GroupNumber,
-    /// Run stats on last column as string and interpret preceding columns as group
-    GroupString,
-    /// Interpret input as CSV with headers and run stats for all
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// Human readable table
+    Table,
+    /// Delimited output, using the output delimiter (default to ,)
     Csv,
+    /// JSON document with typed values and nulls for missing values
+    Json,
+    /// Newline delimited JSON, one object per row
+    Ndjson,
 }
 
 /// Analyze data from stream or file
-/// Preferable chain with sanitize_csv for input conditioning of CSV structured input data
-/// TODO: handle escape characters
+/// Quoted fields (RFC 4180) are supported in the csv and group modes
 #[derive(Parser)]
 struct Cli {
     /// input delimiter
     #[arg(short = 'd', long, default_value_t = ',')]
     input_delimiter: char,
 
-    /// Optional output delimiter, default to human readable table output
+    /// input quote character, only significant at the start of a field
+    #[arg(short = 'q', long, default_value_t = '"')]
+    quote_char: char,
+
+    /// Optional input escape character, makes the next character literal (default to doubled quotes only)
+    #[arg(short = 'e', long)]
+    escape_char: Option<char>,
+
+    /// Optional output delimiter, implies csv format when no format is given
     #[arg(short = 'D', long)]
     output_delimiter: Option<char>,
 
+    /// Optional output format, default to human readable table output (or csv when an output delimiter is given)
+    #[arg(short, long, value_enum)]
+    format: Option<Format>,
+
     /// Optional number of decimals to round for output
     #[arg(short, long, default_value_t = 0)]
     precision: usize,
 
+    /// Optional locale of the numbers (e.g. en, de-DE, fr, de-CH), sets the decimal and thousands separators
+    #[arg(long, value_parser = parse_number_locale)]
+    number_locale: Option<NumberFormat>,
+
+    /// Optional decimal separator of the numbers (default to . or the one of the number locale)
+    #[arg(long)]
+    decimal_separator: Option<char>,
+
+    /// Optional thousands separator of the numbers (default to none or the one of the number locale)
+    #[arg(long)]
+    thousands_separator: Option<char>,
+
+    /// Strip currency symbols and percent signs around numbers
+    #[arg(long, default_value_t = false)]
+    strip_markers: bool,
+
+    /// Optional strftime format of the dates, tried before ISO 8601, RFC 3339 and epoch seconds or millis (repeatable, %s for epoch seconds in csv mode)
+    #[arg(long)]
+    date_format: Vec<String>,
+
+    /// Optional comma separated percentiles (0-100) to output next to the median and IQR
+    #[arg(short = 'P', long, value_delimiter = ',', value_parser = parse_percentile)]
+    percentiles: Vec<f64>,
+
+    /// Optional comma separated statistics to output in table and csv format, in order (e.g. count,empty,mean,p95, default to those of the mode). The length, number and date stats of the string and csv modes are prefixed like length_mean
+    #[arg(short = 'S', long, value_delimiter = ',')]
+    stats: Vec<String>,
+
+    /// Output the variance, skewness, excess kurtosis, coefficient of variation, standard error and confidence interval of the mean next to the standard deviation
+    #[arg(long, default_value_t = false)]
+    moments: bool,
+
+    /// Use the sample (n-1) instead of the population standard deviation and variance
+    #[arg(long, default_value_t = false)]
+    sample_stddev: bool,
+
+    /// Level (0-100) of the confidence interval of the mean
+    #[arg(long, default_value_t = 95.0, value_parser = parse_confidence)]
+    confidence: f64,
+
+    /// Optional histogram of the numbers, as auto:<count> (estimated between min and max), fixed:<min>,<max>,<count> or log:<bins per decade>
+    #[arg(long)]
+    histogram: Option<HistogramBins>,
+
+    /// Output the Pearson and Spearman correlations of the pairs of number columns in csv mode
+    #[arg(long, default_value_t = false)]
+    correlations: bool,
+
+    /// Optional number of most frequent values to output for string stats (approximate counts), or of values in the count modes (default to all)
+    #[arg(short, long, default_value_t = 0)]
+    top: usize,
+
+    /// Optional minimum count of the values to output in the count modes
+    #[arg(long, default_value_t = 0)]
+    min_count: usize,
+
     /// Count zeros as empty when parsing numbers
     #[arg(short, long, default_value_t = false)]
     zero_as_empty: bool,
 
+    /// Optional comma separated values to count as empty, next to the empty string (e.g. NA,NULL,\N,-)
+    #[arg(short, long, value_delimiter = ',')]
+    null_values: Vec<String>,
+
+    /// Match the null values ignoring (ASCII) case
+    #[arg(short = 'i', long, default_value_t = false)]
+    null_values_ignore_case: bool,
+
+    /// Optional comma separated columns (header names or 1-based indices) to group the stats by in csv mode
+    #[arg(short, long, value_delimiter = ',')]
+    group_by: Vec<String>,
+
+    /// Optional comma separated timestamp columns to truncate and group by, as <column>=<hour|day|week|month> with 1-based indices of the group fields in the group modes, output in chronological order
+    #[arg(long, value_delimiter = ',')]
+    time_bucket: Vec<TimeBucket>,
+
+    /// Optional number column to bin into ranges and group by in the group modes, as <column>=width:<width>, <column>=edges:<edge>,<edge>... or <column>=quantiles:<count> with the 1-based index of the group field, output in range order (repeatable)
+    #[arg(long)]
+    bin: Vec<NumberBin>,
+
+    /// Optional comma separated columns to profile in csv mode, as header names, 1-based indices, ranges (2-5, 3-, -4) or glob patterns
+    #[arg(short, long, value_delimiter = ',')]
+    columns: Vec<String>,
+
+    /// Optional comma separated columns to skip in csv mode, referenced like the columns
+    #[arg(short = 'x', long, value_delimiter = ',')]
+    exclude_columns: Vec<String>,
+
+    /// The csv mode input has no header row, name the columns col1..colN
+    #[arg(short = 'H', long, default_value_t = false)]
+    no_header: bool,
+
+    /// Optional comma separated column names for csv mode input without a header row
+    #[arg(short = 'N', long, value_delimiter = ',')]
+    header_names: Vec<String>,
+
+    /// Count the missing trailing fields of short rows as empty in csv mode
+    #[arg(short, long, default_value_t = false)]
+    missing_as_empty: bool,
+
+    /// Optional path of a csv file to write the erroring records to, with line number, column and reason
+    #[arg(short, long)]
+    reject_file: Option<PathBuf>,
+
+    /// Maximum number of erroring records to write per reason and column, the first ones in line order
+    #[arg(long, default_value_t = 1000)]
+    max_rejects: usize,
+
+    /// Optional number of first and of randomly sampled values that failed to parse as numbers to output as examples
+    #[arg(short = 'E', long, default_value_t = 0)]
+    error_examples: usize,
+
+    /// Number of threads for file input, split in chunks of whole records
+    #[arg(short = 'T', long, default_value_t = 1)]
+    threads: usize,
+
     /// What mode to run the program in
     #[arg(value_enum, default_value = "number")]
     mode: Mode,
@@ -54,55 +175,153 @@ struct Cli {
     file: PathBuf,
 }
 
+fn parse_percentile(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(percentile) if (0.0..=100.0).contains(&percentile) => Ok(percentile),
+        _ => Err(format!("{} is not a percentile between 0 and 100", value)),
+    }
+}
+
+fn parse_confidence(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(confidence) if confidence > 0.0 && confidence < 100.0 => Ok(confidence),
+        _ => Err(format!(
+            "{} is not a confidence level between 0 and 100",
+            value
+        )),
+    }
+}
+
+fn parse_number_locale(value: &str) -> Result<NumberFormat, String> {
+    NumberFormat::from_locale(value)
+        .ok_or_else(|| format!("{} is not a known number locale", value))
+}
+
+/// Report an error reading or writing a file and exit
+fn exit_file_error(path: &Path, error: io::Error) -> ! {
+    eprintln!("error: {}: {}", path.display(), error);
+    ::std::process::exit(1);
+}
+
 fn main() {
     let args = Cli::parse();
     let file = args.file;
+    let stats = args
+        .stats
+        .iter()
+        .map(|name| Stat::parse(name, args.mode))
+        .collect::<Result<Vec<Stat>, String>>()
+        .unwrap_or_else(|error| {
+            Cli::command()
+                .error(ErrorKind::ValueValidation, error)
+                .exit()
+        });
 
-    let buf_reader: Box<dyn BufRead> = if file == PathBuf::from("-") {
+    let dialect = Dialect {
+        delimiter: args.input_delimiter,
+        quote: args.quote_char,
+        escape: args.escape_char,
+    };
+
+    let output_format = match (args.format, args.output_delimiter) {
+        (None, None) | (Some(Format::Table), _) => OutputFormat::Table,
+        (None, Some(delimiter)) => OutputFormat::Csv(delimiter),
+        (Some(Format::Csv), delimiter) => OutputFormat::Csv(delimiter.unwrap_or(',')),
+        (Some(Format::Json), _) => OutputFormat::Json,
+        (Some(Format::Ndjson), _) => OutputFormat::Ndjson,
+    };
+
+    let locale_format = args.number_locale.unwrap_or_default();
+    let number_format = NumberFormat {
+        decimal_separator: args
+            .decimal_separator
+            .unwrap_or(locale_format.decimal_separator),
+        thousands_separator: args
+            .thousands_separator
+            .or(locale_format.thousands_separator),
+        strip_markers: args.strip_markers,
+    };
+    if number_format.thousands_separator == Some(number_format.decimal_separator) {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                format!(
+                    "the decimal and thousands separators are both '{}'",
+                    number_format.decimal_separator
+                ),
+            )
+            .exit()
+    }
+
+    let profiler = Profiler::new(args.mode)
+        .dialect(dialect)
+        .zero_as_empty(args.zero_as_empty)
+        .number_format(number_format)
+        .date_formats(args.date_format)
+        .null_values(args.null_values)
+        .null_values_ignore_case(args.null_values_ignore_case)
+        .percentiles(args.percentiles)
+        .moments(args.moments)
+        .sample_stddev(args.sample_stddev)
+        .confidence(args.confidence)
+        .stats(stats)
+        .histogram(args.histogram)
+        .correlations(args.correlations)
+        .top(args.top)
+        .min_count(args.min_count)
+        .threads(args.threads)
+        .group_by(args.group_by)
+        .time_buckets(args.time_bucket)
+        .bins(args.bin)
+        .columns(args.columns)
+        .exclude_columns(args.exclude_columns)
+        .no_header(args.no_header)
+        .header_names(args.header_names)
+        .missing_as_empty(args.missing_as_empty)
+        .rejects(args.reject_file.is_some())
+        .max_rejects(args.max_rejects)
+        .error_examples(args.error_examples);
+
+    // Create the reject file first, not to lose the profiling to an unwritable path
+    let reject_file = args.reject_file.map(|reject_file| {
+        let writer = File::create(&reject_file)
+            .map(BufWriter::new)
+            .unwrap_or_else(|error| exit_file_error(&reject_file, error));
+        (reject_file, writer)
+    });
+
+    let profile = if file.as_os_str() == "-" {
         if stdin().is_terminal() {
             Cli::command().print_help().unwrap();
             ::std::process::exit(2);
         }
-        Box::new(BufReader::new(stdin().lock()))
+        profiler.profile_reader(BufReader::new(stdin().lock()))
     } else {
-        Box::new(BufReader::new(File::open(&file).unwrap()))
-    };
+        profiler.profile_path(&file)
+    }
+    .unwrap_or_else(|error| {
+        if error.kind() == io::ErrorKind::InvalidInput {
+            Cli::command()
+                .error(ErrorKind::ValueValidation, error)
+                .exit()
+        }
+        if file.as_os_str() != "-" {
+            exit_file_error(&file, error)
+        }
+        eprintln!("error: {}", error);
+        ::std::process::exit(1);
+    });
 
-    match args.mode {
-        Mode::Csv => mode::csv::run(
-            buf_reader,
-            args.input_delimiter,
-            args.output_delimiter,
-            args.precision,
-            args.zero_as_empty,
-        ),
-        Mode::GroupString => mode::group_string::run(
-            buf_reader,
-            args.input_delimiter,
-            args.output_delimiter,
-            args.precision,
-            args.zero_as_empty,
-        ),
-        Mode::GroupNumber => mode::group_number::run(
-            buf_reader,
-            args.input_delimiter,
-            args.output_delimiter,
-            args.precision,
-            args.zero_as_empty,
-        ),
-        Mode::Number => mode::number::run(
-            buf_reader,
-            args.input_delimiter,
-            args.output_delimiter,
-            args.precision,
-            args.zero_as_empty,
-        ),
-        Mode::String => mode::string::run(
-            buf_reader,
-            args.input_delimiter,
-            args.output_delimiter,
-            args.precision,
-            args.zero_as_empty,
-        ),
+    if let Some((reject_file, writer)) = reject_file {
+        write_rejects(writer, &profile.rejects, args.input_delimiter)
+            .unwrap_or_else(|error| exit_file_error(&reject_file, error));
+        if profile.dropped_rejects > 0 {
+            eprintln!(
+                "{} more rejects not written, see --max-rejects",
+                profile.dropped_rejects
+            );
+        }
     }
+
+    print(&profile, output_format, args.precision);
 }
@@ -1,5 +1,139 @@
+pub mod count;
 pub mod csv;
+pub mod date;
+pub mod group_count;
 pub mod group_number;
 pub mod group_string;
 pub mod number;
 pub mod string;
+
+use crate::number_bin::compare_bins;
+use crate::output_format::OutputFormat;
+use crate::profile::Profile;
+use crate::render;
+use crate::report::Report;
+use clap::ValueEnum;
+use itertools::Itertools;
+use serde::Serialize;
+use std::cmp::Ordering;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Mode {
+    /// Run stats on input as number
+    Number,
+    /// Run stats on input as string
+    String,
+    /// Run stats on input as date (ISO 8601, RFC 3339, epoch seconds or millis and the date formats)
+    Date,
+    /// Count the distinct values of the input, like sort | uniq -c | sort -rn
+    Count,
+    /// Run stats on last column as number and interpret preceding columns as group
+    GroupNumber,
+    /// Run stats on last column as string and interpret preceding columns as group
+    GroupString,
+    /// Count the distinct values of the last column and interpret preceding columns as group
+    GroupCount,
+    /// Interpret input as CSV with headers and run stats for all
+    Csv,
+}
+
+/// Print the profile to stdout in the given output format, and a summary of the ragged rows to
+/// stderr
+pub fn print(profile: &Profile, output_format: OutputFormat, precision: usize) {
+    render::print(&Report::new(profile), output_format, precision);
+    if let Some(ragged_rows) = profile
+        .ragged_rows
+        .as_ref()
+        .filter(|ragged_rows| ragged_rows.short_rows + ragged_rows.long_rows > 0)
+    {
+        eprintln!(
+            "{} short and {} long rows, first at lines {}",
+            ragged_rows.short_rows,
+            ragged_rows.long_rows,
+            ragged_rows.first_lines.iter().join(", ")
+        );
+    }
+}
+
+/// Order of the group keys: reverse lexical, except chronological for the time buckets and by
+/// range for the number bins at the given positions
+pub(crate) fn compare_groups(
+    a: &[String],
+    b: &[String],
+    time_buckets: &[usize],
+    bins: &[usize],
+) -> Ordering {
+    a.iter()
+        .zip(b)
+        .enumerate()
+        .map(|(position, (a, b))| {
+            if time_buckets.contains(&position) {
+                a.cmp(b)
+            } else if bins.contains(&position) {
+                compare_bins(a, b)
+            } else {
+                b.cmp(a)
+            }
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or_else(|| b.len().cmp(&a.len()))
+}
+
+/// Human readable span like `2d 03:04:05` from seconds
+pub(crate) fn format_span(seconds: f64) -> String {
+    let seconds = seconds.round() as i64;
+    let (days, seconds) = (seconds / 86400, seconds % 86400);
+    let time = format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    );
+    if days > 0 {
+        format!("{}d {}", days, time)
+    } else {
+        time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_groups() {
+        let group = |values: &[&str]| -> Vec<String> {
+            values.iter().map(|value| value.to_string()).collect()
+        };
+        let groups = [
+            group(&["a", "2024-01"]),
+            group(&["b", "2024-02"]),
+            group(&["b", "2024-01"]),
+        ];
+        let sorted = |time_buckets: &[usize], bins: &[usize]| -> Vec<&Vec<String>> {
+            groups
+                .iter()
+                .sorted_by(|a, b| compare_groups(a, b, time_buckets, bins))
+                .collect()
+        };
+        assert_eq!(sorted(&[], &[]), [&groups[1], &groups[2], &groups[0]]);
+        assert_eq!(sorted(&[1], &[]), [&groups[2], &groups[1], &groups[0]]);
+
+        let groups = [group(&["[10, 20)"]), group(&["[-5, 10)"])];
+        assert_eq!(
+            groups
+                .iter()
+                .sorted_by(|a, b| compare_groups(a, b, &[], &[0]))
+                .collect::<Vec<_>>(),
+            [&groups[1], &groups[0]]
+        );
+    }
+
+    #[test]
+    fn test_format_span() {
+        assert_eq!(format_span(0.0), "00:00:00");
+        assert_eq!(format_span(3723.4), "01:02:03");
+        assert_eq!(format_span(2.0 * 86400.0 + 59.6), "2d 00:01:00");
+    }
+}
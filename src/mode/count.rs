@@ -0,0 +1,31 @@
+use crate::count_stats::CountStats;
+use crate::profile::{CountSummary, Profile, ProfileRow};
+use crate::profiler::Profiler;
+
+type Data = CountStats;
+
+pub(crate) fn build_data<I: Iterator<Item = (usize, Vec<String>)>>(
+    records: I,
+    _profiler: &Profiler,
+) -> Data {
+    let mut count_stats = CountStats::new();
+    for (_line, mut record) in records {
+        count_stats.add(record.pop().unwrap_or_default());
+    }
+    count_stats
+}
+
+pub(crate) fn merge_data(data: &mut Data, other: Data) {
+    data.merge(&other);
+}
+
+pub(crate) fn profile(count_stats: Data, profiler: &Profiler) -> Profile {
+    profiler.profile(vec![ProfileRow {
+        counts: Some(CountSummary::new(
+            &count_stats,
+            profiler.top,
+            profiler.min_count,
+        )),
+        ..Default::default()
+    }])
+}
@@ -1,212 +1,325 @@
+use crate::columns::{column_index, column_indices};
+use crate::correlation_stats::CorrelationStats;
+use crate::date_stats::{DateParser, DateStats};
+use crate::mode::compare_groups;
 use crate::number_stats::NumberStats;
-use crate::string_stats::StringStats;
-use cli_table::{
-    format::{HorizontalLine, Justify, Separator, VerticalLine},
-    print_stdout, Cell, CellStruct, Style, Table,
+use crate::profile::{
+    Correlation, DateSummary, Profile, ProfileRow, RaggedSummary, StringSummary, TypeSummary,
 };
-use std::io::BufRead;
-
-type Data = Vec<(String, StringStats, NumberStats, NumberStats)>;
-struct OutputData {
-    output_rows: Vec<OutputRow>,
-    group_length: usize,
-    output_delimiter: Option<char>,
-}
-pub struct OutputRow {
-    pub group_data: Vec<String>,
-    pub stats_data: Vec<String>,
-}
+use crate::profiler::{unknown_column, Profiler};
+use crate::ragged_stats::RaggedStats;
+use crate::reject::{Reject, RejectReason, Rejects};
+use crate::string_stats::StringStats;
+use crate::time_bucket::GroupBuckets;
+use crate::type_stats::{TypeStats, ValueType};
+use itertools::Itertools;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::io;
 
-pub fn run<R: BufRead>(
-    buf_reader: R,
-    input_delimiter: char,
-    output_delimiter: Option<char>,
-    precision: usize,
-    zero_as_empty: bool,
-) {
-    let data = build_data(buf_reader, input_delimiter, zero_as_empty);
-    OutputData::new(data, input_delimiter, output_delimiter, precision).print();
+type ColumnStats = (TypeStats, StringStats, NumberStats, NumberStats, DateStats);
+pub(crate) struct Data {
+    /// Headers of the profiled (non group) columns
+    headers: Vec<String>,
+    groups: HashMap<Vec<String>, Vec<ColumnStats>>,
+    /// Positions of the time buckets in the group keys
+    time_buckets: Vec<usize>,
+    ragged_stats: RaggedStats,
+    rejects: Rejects,
+    /// Correlations of the profiled columns, when requested
+    correlation_stats: Option<CorrelationStats>,
 }
 
-fn build_data<R: BufRead>(buf_reader: R, delimiter: char, zero_as_empty: bool) -> Data {
-    let mut lines_iter = buf_reader.lines();
-    let headers: Vec<String> = lines_iter
-        .next()
-        .unwrap()
-        .expect("at least one row for the header")
-        .split(delimiter)
-        .map(|v| v.to_string())
-        .collect();
-
-    let mut data: Data = headers
-        .into_iter()
-        .map(|header| {
-            (
-                header,
-                StringStats::new(),
-                NumberStats::new(),
-                NumberStats::new(),
-            )
+pub(crate) fn build_data<I: Iterator<Item = (usize, Vec<String>)>>(
+    records: I,
+    profiler: &Profiler,
+) -> io::Result<Data> {
+    let mut records = records.peekable();
+    let headers: Vec<String> = if profiler.has_header() {
+        records.next().unwrap_or_default().1
+    } else {
+        profiler.synthetic_headers(records.peek().map_or(0, |(_line, record)| record.len()))
+    };
+    let mut group_indices: Vec<usize> = profiler
+        .group_by
+        .iter()
+        .map(|reference| {
+            column_index(&headers, reference).ok_or_else(|| unknown_column("group by", reference))
         })
+        .collect::<io::Result<_>>()?;
+    let mut time_buckets = Vec::new();
+    for time_bucket in &profiler.time_buckets {
+        let index = column_index(&headers, &time_bucket.column)
+            .ok_or_else(|| unknown_column("time bucket", &time_bucket.column))?;
+        let position = match group_indices
+            .iter()
+            .position(|&group_index| group_index == index)
+        {
+            Some(position) => position,
+            None => {
+                group_indices.push(index);
+                group_indices.len() - 1
+            }
+        };
+        time_buckets.push((position, time_bucket.unit));
+    }
+    let group_buckets =
+        GroupBuckets::new(time_buckets, DateParser::new(&profiler.date_formats, true));
+    let selected_indices = |references: &[String], option: &str| -> io::Result<Vec<usize>> {
+        references
+            .iter()
+            .map(|reference| {
+                column_indices(&headers, reference).ok_or_else(|| unknown_column(option, reference))
+            })
+            .flatten_ok()
+            .collect()
+    };
+    let included_indices = selected_indices(&profiler.columns, "selected")?;
+    let excluded_indices = selected_indices(&profiler.exclude_columns, "excluded")?;
+    let column_indices: Vec<usize> = (0..headers.len())
+        .filter(|index| profiler.columns.is_empty() || included_indices.contains(index))
+        .filter(|index| !excluded_indices.contains(index) && !group_indices.contains(index))
         .collect();
+    let new_columns = || -> Vec<ColumnStats> {
+        column_indices
+            .iter()
+            .map(|_| {
+                (
+                    TypeStats::new(),
+                    profiler.string_stats(),
+                    profiler.number_stats(),
+                    NumberStats::new(),
+                    DateStats::new(),
+                )
+            })
+            .collect()
+    };
 
-    for line in lines_iter {
-        for ((_header, string_stats, number_stats, length_stats), value) in
-            data.iter_mut().zip(line.unwrap().split(delimiter))
+    // Integers are numbers here, epoch dates need a %s date format
+    let date_parser = DateParser::new(&profiler.date_formats, false);
+    let mut groups = HashMap::new();
+    if group_indices.is_empty() {
+        groups.insert(Vec::new(), new_columns());
+    }
+    let mut ragged_stats = RaggedStats::new();
+    let mut rejects = Rejects::new(profiler.max_rejects);
+    let mut correlation_stats = profiler
+        .correlations
+        .then(|| CorrelationStats::new(column_indices.len()));
+    for (line, mut record) in records {
+        ragged_stats.add(line, record.len(), headers.len());
+        let original_record = profiler.rejects.then(|| record.clone());
+        if let Some(original_record) = original_record.as_ref() {
+            if record.len() != headers.len() {
+                rejects.add(Reject {
+                    line,
+                    column: None,
+                    reason: RejectReason::FieldCountMismatch,
+                    record: original_record.clone(),
+                });
+            }
+        }
+        let mut group: Vec<String> = group_indices
+            .iter()
+            .map(|&index| record.get(index).cloned().unwrap_or_default())
+            .collect();
+        group_buckets.apply(&mut group);
+        let columns = groups.entry(group).or_insert_with(new_columns);
+        let mut numbers = correlation_stats
+            .is_some()
+            .then(|| vec![None; column_indices.len()]);
+        for (
+            position,
+            ((type_stats, string_stats, number_stats, length_stats, date_stats), &index),
+        ) in columns.iter_mut().zip(&column_indices).enumerate()
         {
-            if value.is_empty() {
+            let value = match record.get_mut(index) {
+                Some(value) => std::mem::take(value),
+                None if profiler.missing_as_empty => String::new(),
+                None => break,
+            };
+            if profiler.is_null(&value) {
                 string_stats.add_empty();
                 length_stats.add_empty();
                 number_stats.add_empty();
+                date_stats.add_empty();
             } else {
-                string_stats.add(value.to_string());
+                let number = profiler.number_format.normalize(&value);
+                let parsed_number = number.parse::<f64>();
+                // Numbers are only dates in the given date formats, don't parse them twice
+                let date = if parsed_number.is_ok() && !date_parser.parses_numbers() {
+                    None
+                } else {
+                    date_parser.parse(&value)
+                };
+                type_stats.add_type(ValueType::parsed(
+                    &number,
+                    parsed_number.is_ok(),
+                    date.map(|(_, format)| format),
+                ));
                 length_stats.add(value.len() as f64);
-                match value.parse::<f64>() {
-                    Ok(num) if zero_as_empty && num == 0.0 => number_stats.add_empty(),
-                    Ok(num) => number_stats.add(num),
-                    Err(_) => number_stats.add_error(),
+                match parsed_number {
+                    Ok(num) if profiler.zero_as_empty && num == 0.0 => number_stats.add_empty(),
+                    Ok(num) => {
+                        number_stats.add(num);
+                        if let Some(numbers) = numbers.as_mut() {
+                            numbers[position] = Some(num);
+                        }
+                    }
+                    Err(_) => {
+                        if let Some(original_record) = original_record.as_ref() {
+                            rejects.add(Reject {
+                                line,
+                                column: Some(headers[index].clone()),
+                                reason: RejectReason::ParseFailure,
+                                record: original_record.clone(),
+                            });
+                        }
+                        number_stats.add_invalid(&value);
+                    }
                 };
+                match date {
+                    Some((datetime, format)) => date_stats.add(datetime, format),
+                    None => date_stats.add_error(),
+                }
+                string_stats.add(value);
             };
         }
+        if let (Some(correlation_stats), Some(numbers)) = (correlation_stats.as_mut(), numbers) {
+            correlation_stats.add(&numbers);
+        }
     }
-    data
+    Ok(Data {
+        headers: column_indices
+            .into_iter()
+            .map(|index| headers[index].clone())
+            .collect(),
+        groups,
+        time_buckets: group_buckets.positions(),
+        ragged_stats,
+        rejects,
+        correlation_stats,
+    })
 }
 
-impl OutputData {
-    fn new(
-        data: Data,
-        _input_delimiter: char,
-        output_delimiter: Option<char>,
-        precision: usize,
-    ) -> Self {
-        let output_rows: Vec<OutputRow> = data
-            .into_iter()
-            .map(|(header, string_stats, number_stats, length_stats)| {
-                let stats_data = vec![
-                    format!("{}", string_stats.count()),
-                    format!("{}", string_stats.cardinality()),
-                    format!("{}", string_stats.empty_count()),
-                    format!("{}", string_stats.min().unwrap_or("".to_string())),
-                    format!("{}", string_stats.max().unwrap_or("".to_string())),
-                    format!("{}", number_stats.empty_count()),
-                    format!("{}", number_stats.error_count()),
-                    format!("{:.*}", precision, number_stats.min().unwrap_or(0.0),),
-                    format!("{:.*}", precision, number_stats.max().unwrap_or(0.0),),
-                    format!("{:.e}", number_stats.sum()),
-                    format!("{:.*}", precision, number_stats.mean()),
-                    format!("{:.*}", precision, number_stats.stddev()),
-                    format!("{:.*}", 0, length_stats.min().unwrap_or(0.0),),
-                    format!("{:.*}", 0, length_stats.max().unwrap_or(0.0),),
-                    format!("{:.*}", precision, length_stats.mean()),
-                    format!("{:.*}", precision, length_stats.stddev()),
-                ];
-                OutputRow {
-                    group_data: [header].to_vec(),
-                    stats_data,
+pub(crate) fn merge_data(data: &mut Data, other: Data) {
+    data.ragged_stats.merge(&other.ragged_stats);
+    data.rejects.merge(other.rejects);
+    if let (Some(correlation_stats), Some(other_correlation_stats)) = (
+        data.correlation_stats.as_mut(),
+        other.correlation_stats.as_ref(),
+    ) {
+        correlation_stats.merge(other_correlation_stats);
+    }
+    for (group, other_columns) in other.groups {
+        match data.groups.entry(group) {
+            Entry::Occupied(mut entry) => {
+                for (
+                    (type_stats, string_stats, number_stats, length_stats, date_stats),
+                    (
+                        other_type_stats,
+                        other_string_stats,
+                        other_number_stats,
+                        other_length_stats,
+                        other_date_stats,
+                    ),
+                ) in entry.get_mut().iter_mut().zip(other_columns)
+                {
+                    type_stats.merge(&other_type_stats);
+                    string_stats.merge(&other_string_stats);
+                    number_stats.merge(&other_number_stats);
+                    length_stats.merge(&other_length_stats);
+                    date_stats.merge(&other_date_stats);
                 }
-            })
-            .collect();
-        let group_length = output_rows.first().unwrap().group_data.len();
-        Self {
-            output_rows,
-            group_length,
-            output_delimiter,
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(other_columns);
+            }
         }
     }
+}
+
+pub(crate) fn profile(data: Data, profiler: &Profiler) -> Profile {
+    let Data {
+        headers,
+        groups,
+        time_buckets,
+        ragged_stats,
+        mut rejects,
+        correlation_stats,
+    } = data;
 
-    fn print(&self) {
-        match self.output_delimiter {
-            None => self.print_table(),
-            Some(delimiter) => self.print_csv(delimiter),
+    // Parse failures are only rejects in the columns inferred as numbers
+    let mut column_types = vec![TypeStats::new(); headers.len()];
+    for columns in groups.values() {
+        for (column_type, (type_stats, ..)) in column_types.iter_mut().zip(columns) {
+            column_type.merge(type_stats);
         }
     }
+    let number_positions: Vec<usize> = column_types
+        .iter()
+        .positions(|type_stats| {
+            matches!(
+                type_stats.inferred_type(),
+                ValueType::Integer | ValueType::Float
+            )
+        })
+        .collect();
+    let number_headers: Vec<&String> = number_positions
+        .iter()
+        .map(|&position| &headers[position])
+        .collect();
+    rejects.retain(|reason, column| {
+        reason != RejectReason::ParseFailure
+            || column.is_some_and(|column| number_headers.contains(&column))
+    });
+    let (rejects, dropped_rejects) = rejects.into_parts();
 
-    fn print_table(&self) {
-        let separator = Separator::builder()
-            .title(Some(HorizontalLine::default()))
-            .column(Some(VerticalLine::default()))
-            .build();
-
-        let mut group_title: Vec<CellStruct> = [""]
-            .iter()
-            .cycle()
-            .take(self.group_length)
-            .map(|v| v.cell())
-            .collect();
-        let mut number_title: Vec<CellStruct> = vec![
-            "Count".cell().justify(Justify::Right).bold(true),
-            "Cardinality".cell().justify(Justify::Right).bold(true),
-            "String Empty".cell().justify(Justify::Right).bold(true),
-            "String Min".cell().justify(Justify::Right).bold(true),
-            "String Max".cell().justify(Justify::Right).bold(true),
-            "Number Empty".cell().justify(Justify::Right).bold(true),
-            "Number Error".cell().justify(Justify::Right).bold(true),
-            "Number Min".cell().justify(Justify::Right).bold(true),
-            "Number Max".cell().justify(Justify::Right).bold(true),
-            "Number Sum".cell().justify(Justify::Right).bold(true),
-            "Number Mean".cell().justify(Justify::Right).bold(true),
-            "Number StdDev".cell().justify(Justify::Right).bold(true),
-            "Length Min".cell().justify(Justify::Right).bold(true),
-            "Length Max".cell().justify(Justify::Right).bold(true),
-            "Length Mean".cell().justify(Justify::Right).bold(true),
-            "Length StdDev".cell().justify(Justify::Right).bold(true),
-        ];
-        group_title.append(&mut number_title);
-
-        let table = self
-            .output_rows
-            .iter()
-            .map(|output_row| {
-                let mut group_data: Vec<CellStruct> =
-                    output_row.group_data.iter().map(|v| v.cell()).collect();
-                let mut number_data: Vec<CellStruct> = output_row
-                    .stats_data
-                    .iter()
-                    .map(|v| v.cell().justify(Justify::Right))
-                    .collect();
-                group_data.append(&mut number_data);
-                group_data
-            })
-            .table()
-            .separator(separator)
-            .title(group_title)
-            .bold(true);
-
-        print_stdout(table).unwrap();
-    }
+    let rows = groups
+        .into_iter()
+        .sorted_by(|a, b| compare_groups(&a.0, &b.0, &time_buckets, &[]))
+        .flat_map(|(group, columns)| {
+            headers.iter().zip(columns).map(
+                move |(
+                    header,
+                    (type_stats, string_stats, number_stats, length_stats, date_stats),
+                )| {
+                    ProfileRow {
+                        group: group.clone(),
+                        column: Some(header.clone()),
+                        inferred_type: Some(TypeSummary::new(&type_stats)),
+                        string: Some(StringSummary::new(&string_stats)),
+                        number: Some(profiler.number_summary(&number_stats)),
+                        length: Some(profiler.number_summary(&length_stats)),
+                        // Only for the columns with dates
+                        date: (date_stats.count() > 0).then(|| DateSummary::new(&date_stats)),
+                        counts: None,
+                    }
+                },
+            )
+        })
+        .collect();
 
-    fn print_csv(&self, delimiter: char) {
-        let delimiter = delimiter.to_string();
-        println!(
-            "{}{}",
-            delimiter.repeat(self.group_length),
-            [
-                "count",
-                "cardinality",
-                "string_empty",
-                "string_min",
-                "string_max",
-                "number_empty",
-                "number_error",
-                "number_min",
-                "number_max",
-                "number_sum",
-                "number_mean",
-                "number_stddev",
-                "length_min",
-                "length_max",
-                "length_mean",
-                "length_stddev"
-            ]
-            .join(&delimiter)
-        );
-        for row in self.output_rows.iter() {
-            println!(
-                "{}{}{}",
-                row.group_data.join(&delimiter),
-                delimiter,
-                row.stats_data.join(&delimiter)
-            );
-        }
+    // Correlations of the pairs of columns inferred as numbers
+    let correlations = correlation_stats
+        .map(|correlation_stats| {
+            number_positions
+                .iter()
+                .tuple_combinations()
+                .map(|(&i, &j)| Correlation {
+                    columns: [headers[i].clone(), headers[j].clone()],
+                    count: correlation_stats.count(i, j),
+                    pearson: correlation_stats.pearson(i, j),
+                    spearman: correlation_stats.spearman(i, j),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Profile {
+        ragged_rows: Some(RaggedSummary::new(&ragged_stats)),
+        rejects,
+        dropped_rejects,
+        correlations,
+        ..profiler.profile(rows)
     }
 }
@@ -0,0 +1,55 @@
+use crate::date_stats::{DateParser, DateStats};
+use crate::profile::{DateSummary, Profile, ProfileRow};
+use crate::profiler::Profiler;
+use crate::reject::{Reject, RejectReason, Rejects};
+
+type Data = (DateStats, Rejects);
+
+pub(crate) fn build_data<I: Iterator<Item = (usize, Vec<String>)>>(
+    records: I,
+    profiler: &Profiler,
+) -> Data {
+    let date_parser = DateParser::new(&profiler.date_formats, true);
+    let mut date_stats = DateStats::new();
+    let mut rejects = Rejects::new(profiler.max_rejects);
+    for (line, mut record) in records {
+        let value = record.pop().unwrap_or_default();
+        if profiler.is_null(&value) {
+            date_stats.add_empty();
+        } else {
+            match date_parser.parse(&value) {
+                Some((datetime, format)) => date_stats.add(datetime, format),
+                None => {
+                    date_stats.add_error();
+                    if profiler.rejects {
+                        rejects.add(Reject {
+                            line,
+                            column: None,
+                            reason: RejectReason::ParseFailure,
+                            record: vec![value],
+                        });
+                    }
+                }
+            }
+        }
+    }
+    (date_stats, rejects)
+}
+
+pub(crate) fn merge_data(data: &mut Data, other: Data) {
+    data.0.merge(&other.0);
+    data.1.merge(other.1);
+}
+
+pub(crate) fn profile(data: Data, profiler: &Profiler) -> Profile {
+    let (date_stats, rejects) = data;
+    let (rejects, dropped_rejects) = rejects.into_parts();
+    Profile {
+        rejects,
+        dropped_rejects,
+        ..profiler.profile(vec![ProfileRow {
+            date: Some(DateSummary::new(&date_stats)),
+            ..Default::default()
+        }])
+    }
+}
@@ -0,0 +1,76 @@
+use crate::count_stats::CountStats;
+use crate::mode::compare_groups;
+use crate::profile::{CountSummary, Profile, ProfileRow};
+use crate::profiler::Profiler;
+use crate::reject::{Reject, RejectReason, Rejects};
+use itertools::Itertools;
+use std::collections::HashMap;
+use std::io;
+
+type Data = (HashMap<Vec<String>, CountStats>, Rejects);
+
+pub(crate) fn build_data<I: Iterator<Item = (usize, Vec<String>)>>(
+    records: I,
+    profiler: &Profiler,
+) -> io::Result<Data> {
+    let group_buckets = profiler.group_buckets()?;
+    let group_bins = profiler.group_bins()?;
+    let mut groups: HashMap<Vec<String>, CountStats> = HashMap::new();
+    let mut rejects = Rejects::new(profiler.max_rejects);
+    for (line, mut record) in records {
+        if record.len() < 2 {
+            groups
+                .entry(vec!["<INVALID>".to_string()])
+                .or_default()
+                .add(record.first().cloned().unwrap_or_default());
+            if profiler.rejects {
+                rejects.add(Reject {
+                    line,
+                    column: None,
+                    reason: RejectReason::MissingDelimiter,
+                    record,
+                });
+            }
+            continue;
+        }
+        let value = record.pop().unwrap_or_default();
+        group_buckets.apply(&mut record);
+        group_bins.apply(&mut record);
+        groups.entry(record).or_default().add(value);
+    }
+    Ok((groups, rejects))
+}
+
+pub(crate) fn merge_data(data: &mut Data, other: Data) {
+    for (group, other_count_stats) in other.0 {
+        data.0.entry(group).or_default().merge(&other_count_stats);
+    }
+    data.1.merge(other.1);
+}
+
+pub(crate) fn profile(data: Data, profiler: &Profiler) -> io::Result<Profile> {
+    let (groups, rejects) = data;
+    profiler.check_group_fields(groups.keys())?;
+    let time_buckets = profiler.group_buckets()?.positions();
+    let bins = profiler.group_bins()?.positions();
+    let (rejects, dropped_rejects) = rejects.into_parts();
+    Ok(Profile {
+        rejects,
+        dropped_rejects,
+        ..profiler.profile(
+            groups
+                .into_iter()
+                .sorted_by(|a, b| compare_groups(&a.0, &b.0, &time_buckets, &bins))
+                .map(|(group, count_stats)| ProfileRow {
+                    group,
+                    counts: Some(CountSummary::new(
+                        &count_stats,
+                        profiler.top,
+                        profiler.min_count,
+                    )),
+                    ..Default::default()
+                })
+                .collect(),
+        )
+    })
+}
@@ -0,0 +1,251 @@
+/// Count, mean and sums of the powers 2 to 4 of the deviations from the mean, updated in a single
+/// numerically stable pass and mergeable across parts of the input
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Moments {
+    count: usize,
+    mean: f64,
+    m2: f64,
+    m3: f64,
+    m4: f64,
+}
+
+impl Moments {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn add(&mut self, num: f64) {
+        let count_a = self.count as f64;
+        self.count += 1;
+        let count = self.count as f64;
+        let delta = num - self.mean;
+        let delta_n = delta / count;
+        let delta_n2 = delta_n * delta_n;
+        let term = delta * delta_n * count_a;
+        self.mean += delta_n;
+        self.m4 += term * delta_n2 * (count * count - 3.0 * count + 3.0) + 6.0 * delta_n2 * self.m2
+            - 4.0 * delta_n * self.m3;
+        self.m3 += term * delta_n * (count - 2.0) - 3.0 * delta_n * self.m2;
+        self.m2 += term;
+    }
+    /// Combine with the moments of another part of the input, as if all values were added to
+    /// this one
+    pub fn merge(&mut self, other: &Self) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = *other;
+            return;
+        }
+        let (count_a, count_b) = (self.count as f64, other.count as f64);
+        let count = count_a + count_b;
+        let delta = other.mean - self.mean;
+        let delta2 = delta * delta;
+        let m2 = self.m2 + other.m2 + delta2 * count_a * count_b / count;
+        let m3 = self.m3
+            + other.m3
+            + delta2 * delta * count_a * count_b * (count_a - count_b) / (count * count)
+            + 3.0 * delta * (count_a * other.m2 - count_b * self.m2) / count;
+        let m4 = self.m4
+            + other.m4
+            + delta2
+                * delta2
+                * count_a
+                * count_b
+                * (count_a * count_a - count_a * count_b + count_b * count_b)
+                / (count * count * count)
+            + 6.0 * delta2 * (count_a * count_a * other.m2 + count_b * count_b * self.m2)
+                / (count * count)
+            + 4.0 * delta * (count_a * other.m3 - count_b * self.m3) / count;
+        self.mean += delta * count_b / count;
+        (self.m2, self.m3, self.m4) = (m2, m3, m4);
+        self.count += other.count;
+    }
+    pub fn count(&self) -> usize {
+        self.count
+    }
+    /// Mean (0 without values)
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+    /// Population variance, or sample variance (n - 1 denominator) when `sample` (0 without
+    /// enough values)
+    pub fn variance(&self, sample: bool) -> f64 {
+        let denominator = if sample {
+            self.count.saturating_sub(1)
+        } else {
+            self.count
+        };
+        if denominator == 0 {
+            0.0
+        } else {
+            self.m2 / denominator as f64
+        }
+    }
+    /// Population skewness, None when all values are equal
+    pub fn skewness(&self) -> Option<f64> {
+        (self.m2 > 0.0).then(|| (self.count as f64).sqrt() * self.m3 / self.m2.powf(1.5))
+    }
+    /// Population excess kurtosis (0 for a normal distribution), None when all values are equal
+    pub fn kurtosis(&self) -> Option<f64> {
+        (self.m2 > 0.0).then(|| self.count as f64 * self.m4 / (self.m2 * self.m2) - 3.0)
+    }
+    /// Standard error of the mean, from the sample standard deviation
+    pub fn std_error(&self) -> Option<f64> {
+        (self.count >= 2).then(|| (self.variance(true) / self.count as f64).sqrt())
+    }
+    /// Confidence interval of the mean at the given level (0-100), from the Student t
+    /// distribution
+    pub fn mean_interval(&self, confidence: f64) -> Option<(f64, f64)> {
+        let std_error = self.std_error()?;
+        let t = student_t_quantile(0.5 + confidence / 200.0, self.count - 1);
+        Some((self.mean - t * std_error, self.mean + t * std_error))
+    }
+}
+
+/// Quantile of the standard normal distribution (Acklam's approximation, relative error below
+/// 1.2e-9)
+fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.38357751867269e2,
+        -3.066479806614716e1,
+        2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838,
+        -2.549732539343734,
+        4.374664141464968,
+        2.938163982698783,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3,
+        3.224671290700398e-1,
+        2.445134137142996,
+        3.754408661907416,
+    ];
+    let polynomial = |coefficients: &[f64], x: f64| {
+        coefficients
+            .iter()
+            .fold(0.0, |value, coefficient| value * x + coefficient)
+    };
+    let tail = |p: f64| {
+        let q = (-2.0 * p.ln()).sqrt();
+        polynomial(&C, q) / (polynomial(&D, q) * q + 1.0)
+    };
+    if p < 0.02425 {
+        tail(p)
+    } else if p > 1.0 - 0.02425 {
+        -tail(1.0 - p)
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        polynomial(&A, r) * q / (polynomial(&B, r) * r + 1.0)
+    }
+}
+
+/// Quantile of the Student t distribution, exact for 1 and 2 degrees of freedom and from the
+/// Cornish-Fisher expansion beyond (within 0.2% for 3 degrees of freedom)
+fn student_t_quantile(p: f64, degrees: usize) -> f64 {
+    match degrees {
+        1 => (std::f64::consts::PI * (p - 0.5)).tan(),
+        2 => (2.0 * p - 1.0) / (2.0 * p * (1.0 - p)).sqrt(),
+        _ => {
+            let (z, n) = (normal_quantile(p), degrees as f64);
+            let (z2, z3) = (z * z, z * z * z);
+            let (z5, z7, z9) = (z3 * z2, z3 * z2 * z2, z3 * z3 * z3);
+            z + (z3 + z) / (4.0 * n)
+                + (5.0 * z5 + 16.0 * z3 + 3.0 * z) / (96.0 * n * n)
+                + (3.0 * z7 + 19.0 * z5 + 17.0 * z3 - 15.0 * z) / (384.0 * n * n * n)
+                + (79.0 * z9 + 776.0 * z7 + 1482.0 * z5 - 1920.0 * z3 - 945.0 * z)
+                    / (92160.0 * n * n * n * n)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moments(values: &[f64]) -> Moments {
+        let mut moments = Moments::new();
+        for &value in values {
+            moments.add(value);
+        }
+        moments
+    }
+
+    #[test]
+    fn test_moments() {
+        let moments = moments(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]);
+        assert_eq!(moments.count(), 8);
+        assert_eq!(moments.mean(), 5.0);
+        assert!((moments.variance(false) - 4.0).abs() < 1e-12);
+        assert!((moments.variance(true) - 32.0 / 7.0).abs() < 1e-12);
+        assert!((moments.skewness().unwrap() - 0.65625).abs() < 1e-12);
+        assert!((moments.kurtosis().unwrap() + 0.21875).abs() < 1e-12);
+        assert!((moments.std_error().unwrap() - (4.0f64 / 7.0).sqrt()).abs() < 1e-12);
+        let (lower, upper) = moments.mean_interval(95.0).unwrap();
+        // t(0.975, 7) = 2.3646
+        assert!((upper - 5.0 - 2.3646 * (4.0f64 / 7.0).sqrt()).abs() < 1e-3);
+        assert!((5.0 - lower - (upper - 5.0)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_degenerate() {
+        let empty = Moments::new();
+        assert_eq!(empty.mean(), 0.0);
+        assert_eq!(empty.variance(true), 0.0);
+        assert_eq!(empty.std_error(), None);
+        let constant = moments(&[3.0, 3.0]);
+        assert_eq!(constant.skewness(), None);
+        assert_eq!(constant.kurtosis(), None);
+        assert_eq!(constant.mean_interval(95.0), Some((3.0, 3.0)));
+    }
+
+    #[test]
+    fn test_merge() {
+        let values = [3.0, 1.0, 4.0, 1.0, 5.0, 9.0, 2.0, 6.0, 5.0, 3.0];
+        let all = moments(&values);
+        let mut left = moments(&values[..3]);
+        left.merge(&moments(&values[3..]));
+        left.merge(&Moments::new());
+        assert_eq!(left.count(), all.count());
+        assert!((left.mean() - all.mean()).abs() < 1e-12);
+        assert!((left.variance(false) - all.variance(false)).abs() < 1e-12);
+        assert!((left.skewness().unwrap() - all.skewness().unwrap()).abs() < 1e-12);
+        assert!((left.kurtosis().unwrap() - all.kurtosis().unwrap()).abs() < 1e-12);
+        let mut empty = Moments::new();
+        empty.merge(&all);
+        assert_eq!(empty, all);
+    }
+
+    #[test]
+    fn test_stable() {
+        // Large offset with a small spread, which breaks the naive sums of powers
+        let moments = moments(&[1e9 + 4.0, 1e9 + 7.0, 1e9 + 13.0, 1e9 + 16.0]);
+        assert!((moments.variance(true) - 30.0).abs() < 1e-6);
+        assert!(moments.skewness().unwrap().abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_quantiles() {
+        assert!((normal_quantile(0.975) - 1.959964).abs() < 1e-6);
+        assert!((normal_quantile(0.001) + 3.090232).abs() < 1e-6);
+        assert!((student_t_quantile(0.975, 1) - 12.7062).abs() < 1e-3);
+        assert!((student_t_quantile(0.975, 2) - 4.3027).abs() < 1e-3);
+        assert!((student_t_quantile(0.975, 10) - 2.2281).abs() < 1e-3);
+        assert!((student_t_quantile(0.995, 30) - 2.7500).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,323 @@
+use crate::number_format::NumberFormat;
+use crate::t_digest::TDigest;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// How the values of a number column are binned
+#[derive(Clone, Debug, PartialEq)]
+pub enum Binning {
+    /// Bins of the given width, starting at multiples of it
+    Width(f64),
+    /// Bins between the given ascending edges, plus one below and one above them
+    Edges(Vec<f64>),
+    /// The given number of bins holding about the same number of records, between edges estimated
+    /// in a first pass over the input
+    Quantiles(usize),
+}
+
+/// Number column (1-based index of the group fields) whose values are binned into ranges to
+/// group by
+#[derive(Clone, Debug, PartialEq)]
+pub struct NumberBin {
+    pub column: String,
+    pub binning: Binning,
+}
+
+impl FromStr for NumberBin {
+    type Err = String;
+
+    /// Parse `<column>=width:<width>`, `<column>=edges:<edge>,<edge>...` or
+    /// `<column>=quantiles:<count>`
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let error = || {
+            format!(
+                "{} is not like <column>=width:<width>, <column>=edges:<edge>,<edge>... or <column>=quantiles:<count>",
+                value
+            )
+        };
+        let (column, binning) = value.split_once('=').ok_or_else(error)?;
+        let binning = match binning.split_once(':').ok_or_else(error)? {
+            ("width", width) => match width.parse::<f64>() {
+                Ok(width) if width > 0.0 => Binning::Width(width),
+                _ => return Err(error()),
+            },
+            ("edges", edges) => {
+                let edges = edges
+                    .split(',')
+                    .map(|edge| edge.parse::<f64>().map_err(|_| error()))
+                    .collect::<Result<Vec<f64>, String>>()?;
+                if !edges.windows(2).all(|pair| pair[0] < pair[1]) {
+                    return Err(error());
+                }
+                Binning::Edges(edges)
+            }
+            ("quantiles", count) => match count.parse::<usize>() {
+                Ok(count) if count > 0 => Binning::Quantiles(count),
+                _ => return Err(error()),
+            },
+            _ => return Err(error()),
+        };
+        Ok(Self {
+            column: column.to_string(),
+            binning,
+        })
+    }
+}
+
+/// Label of the range `[lower, upper)`, or `[lower, upper]` for a closed range
+fn label(lower: f64, upper: f64, closed: bool) -> String {
+    let lower_bracket = if lower == f64::NEG_INFINITY { '(' } else { '[' };
+    let upper_bracket = if closed { ']' } else { ')' };
+    format!("{}{}, {}{}", lower_bracket, lower, upper, upper_bracket)
+}
+
+/// Round a bin bound to the decimals of the width, dropping float artifacts like
+/// `0.30000000000000004` from multiples of `0.1`
+fn round_to_width(bound: f64, width: f64) -> f64 {
+    let decimals = width
+        .to_string()
+        .split_once('.')
+        .map_or(0, |(_integer, decimals)| decimals.len());
+    let scale = 10f64.powi(decimals as i32);
+    let rounded = (bound * scale).round() / scale;
+    // No negative zero label
+    if rounded.is_finite() {
+        rounded + 0.0
+    } else {
+        bound
+    }
+}
+
+/// Lower bound of a bin label, None for empty and invalid values
+fn lower_bound(label: &str) -> Option<f64> {
+    label
+        .strip_prefix(['[', '('])?
+        .split_once(',')?
+        .0
+        .parse()
+        .ok()
+}
+
+/// Order of bin labels by range, empty and invalid values last
+pub(crate) fn compare_bins(a: &str, b: &str) -> Ordering {
+    match (lower_bound(a), lower_bound(b)) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.cmp(b),
+    }
+}
+
+/// Edges of `count` bins holding about the same number of values: the min, the estimated
+/// quantiles (to 6 significant digits for readable labels) and the max, deduplicated
+pub(crate) fn quantile_edges(t_digest: &TDigest, count: usize) -> Vec<f64> {
+    let mut edges: Vec<f64> = (0..=count)
+        .filter_map(|part| {
+            let quantile = t_digest.quantile(part as f64 / count as f64)?;
+            Some(if part == 0 || part == count {
+                quantile
+            } else {
+                round_significant(quantile, 6)
+            })
+        })
+        .collect();
+    edges.dedup();
+    edges
+}
+
+/// Round to the given number of significant digits
+fn round_significant(value: f64, digits: i32) -> f64 {
+    if value == 0.0 || !value.is_finite() {
+        return value;
+    }
+    let scale = 10f64.powi(digits - 1 - value.abs().log10().floor() as i32);
+    let rounded = (value * scale).round() / scale;
+    if rounded.is_finite() {
+        rounded
+    } else {
+        value
+    }
+}
+
+/// Label of the quantile bin of a number, the max in the last bin and a single value in a
+/// `[value, value]` bin. None without edges.
+fn quantile_label(edges: &[f64], num: f64) -> Option<String> {
+    let last = edges.len().checked_sub(1)?;
+    let lower = (edges.partition_point(|&edge| edge <= num).max(1) - 1).min(last.saturating_sub(1));
+    let upper = (lower + 1).min(last);
+    Some(label(edges[lower], edges[upper], upper == last))
+}
+
+/// Number bins resolved to positions in the group keys
+pub(crate) struct GroupBins {
+    bins: Vec<(usize, Binning)>,
+    /// Edges of the quantile bins by position
+    quantile_edges: HashMap<usize, Vec<f64>>,
+    number_format: NumberFormat,
+}
+
+impl GroupBins {
+    pub(crate) fn new(
+        bins: Vec<(usize, Binning)>,
+        quantile_edges: HashMap<usize, Vec<f64>>,
+        number_format: NumberFormat,
+    ) -> Self {
+        Self {
+            bins,
+            quantile_edges,
+            number_format,
+        }
+    }
+
+    /// Positions of the bins in the group keys
+    pub(crate) fn positions(&self) -> Vec<usize> {
+        self.bins
+            .iter()
+            .map(|(position, _binning)| *position)
+            .collect()
+    }
+
+    /// Replace the numbers of the group key by their bin, `<INVALID>` when not a number
+    pub(crate) fn apply(&self, group: &mut [String]) {
+        for (position, binning) in &self.bins {
+            let Some(value) = group.get_mut(*position).filter(|value| !value.is_empty()) else {
+                continue;
+            };
+            *value = match self.number_format.parse(value) {
+                Ok(num) if num.is_nan() => "<INVALID>".to_string(),
+                Ok(num) => match binning {
+                    Binning::Width(width) => {
+                        let lower = (num / width).floor() * width;
+                        label(
+                            round_to_width(lower, *width),
+                            round_to_width(lower + width, *width),
+                            false,
+                        )
+                    }
+                    Binning::Edges(edges) => {
+                        let upper = edges.partition_point(|&edge| edge <= num);
+                        label(
+                            upper
+                                .checked_sub(1)
+                                .map_or(f64::NEG_INFINITY, |lower| edges[lower]),
+                            edges.get(upper).copied().unwrap_or(f64::INFINITY),
+                            false,
+                        )
+                    }
+                    Binning::Quantiles(_) => self
+                        .quantile_edges
+                        .get(position)
+                        .and_then(|edges| quantile_label(edges, num))
+                        .unwrap_or_else(|| "<INVALID>".to_string()),
+                },
+                Err(_) => "<INVALID>".to_string(),
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            "2=width:10".parse(),
+            Ok(NumberBin {
+                column: "2".to_string(),
+                binning: Binning::Width(10.0)
+            })
+        );
+        assert_eq!(
+            "1=edges:0,18,65".parse::<NumberBin>().unwrap().binning,
+            Binning::Edges(vec![0.0, 18.0, 65.0])
+        );
+        assert_eq!(
+            "1=quantiles:4".parse::<NumberBin>().unwrap().binning,
+            Binning::Quantiles(4)
+        );
+        assert!("1=edges:3,1".parse::<NumberBin>().is_err());
+        assert!("1=width:0".parse::<NumberBin>().is_err());
+        assert!("1".parse::<NumberBin>().is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        let bins = GroupBins::new(
+            vec![
+                (0, Binning::Width(10.0)),
+                (1, Binning::Edges(vec![0.0, 18.0])),
+            ],
+            HashMap::new(),
+            NumberFormat::default(),
+        );
+        let mut key = group(&["25", "18"]);
+        bins.apply(&mut key);
+        assert_eq!(key, ["[20, 30)", "[18, inf)"]);
+        let mut key = group(&["-5", "-1"]);
+        bins.apply(&mut key);
+        assert_eq!(key, ["[-10, 0)", "(-inf, 0)"]);
+        let mut key = group(&["x", ""]);
+        bins.apply(&mut key);
+        assert_eq!(key, ["<INVALID>", ""]);
+
+        let bins = GroupBins::new(
+            vec![(0, Binning::Width(0.1))],
+            HashMap::new(),
+            NumberFormat::default(),
+        );
+        let mut key = group(&["0.35"]);
+        bins.apply(&mut key);
+        assert_eq!(key, ["[0.3, 0.4)"]);
+        let mut key = group(&["-0"]);
+        bins.apply(&mut key);
+        assert_eq!(key, ["[0, 0.1)"]);
+    }
+
+    #[test]
+    fn test_quantiles() {
+        let mut t_digest = TDigest::new();
+        for value in [1.0, 2.0, 3.0, 4.0] {
+            t_digest.add(value);
+        }
+        let edges = quantile_edges(&t_digest, 2);
+        assert_eq!(edges, [1.0, 2.5, 4.0]);
+        let bins = GroupBins::new(
+            vec![(0, Binning::Quantiles(2))],
+            HashMap::from([(0, edges)]),
+            NumberFormat::default(),
+        );
+        let labels: Vec<String> = ["1", "2", "3", "4", "x"]
+            .into_iter()
+            .map(|value| {
+                let mut key = group(&[value]);
+                bins.apply(&mut key);
+                key.remove(0)
+            })
+            .collect();
+        assert_eq!(
+            labels,
+            ["[1, 2.5)", "[1, 2.5)", "[2.5, 4]", "[2.5, 4]", "<INVALID>"]
+        );
+        assert_eq!(quantile_label(&[3.0], 3.0), Some("[3, 3]".to_string()));
+        assert_eq!(round_significant(250.38384068744818, 6), 250.384);
+        assert_eq!(round_significant(-0.000123456789, 3), -0.000123);
+        assert_eq!(quantile_label(&[], 3.0), None);
+    }
+
+    #[test]
+    fn test_compare_bins() {
+        let mut labels = ["<INVALID>", "[10, 20)", "(-inf, 0)", "[-5, 10)", ""];
+        labels.sort_by(|a, b| compare_bins(a, b));
+        assert_eq!(
+            labels,
+            ["(-inf, 0)", "[-5, 10)", "[10, 20)", "", "<INVALID>"]
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use std::borrow::Cow;
+use std::num::ParseFloatError;
+
+/// Currency symbols stripped along with percent signs
+const CURRENCY_SYMBOLS: [char; 12] = ['$', '€', '£', '¥', '₹', '₽', '₩', '₺', '₪', '฿', '¢', '₣'];
+/// No-break spaces used as thousands separator next to the regular space
+const NO_BREAK_SPACES: [char; 2] = ['\u{a0}', '\u{202f}'];
+
+/// How numbers are written: decimal and thousands separators, and whether currency symbols and
+/// percent signs around the number are stripped
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct NumberFormat {
+    pub decimal_separator: char,
+    pub thousands_separator: Option<char>,
+    pub strip_markers: bool,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self {
+            decimal_separator: '.',
+            thousands_separator: None,
+            strip_markers: false,
+        }
+    }
+}
+
+impl NumberFormat {
+    /// Separators of a locale like `en`, `de-DE`, `fr_FR` or `de-CH`
+    pub fn from_locale(locale: &str) -> Option<Self> {
+        let locale = locale.to_lowercase().replace('_', "-");
+        let (language, region) = locale.split_once('-').unwrap_or((&locale, ""));
+        let (decimal_separator, thousands_separator) = match (language, region) {
+            (_, "ch") | (_, "li") => ('.', '\''),
+            ("en" | "ja" | "ko" | "zh" | "he" | "th" | "hi" | "ga" | "mt", _) => ('.', ','),
+            ("de" | "nl" | "it" | "es" | "pt" | "da" | "id" | "tr" | "el" | "ro" | "sl", _) => {
+                (',', '.')
+            }
+            ("fr" | "sv" | "fi" | "nb" | "no" | "pl" | "cs" | "sk" | "ru" | "uk" | "hu", _) => {
+                (',', ' ')
+            }
+            _ => return None,
+        };
+        Some(Self {
+            decimal_separator,
+            thousands_separator: Some(thousands_separator),
+            ..Self::default()
+        })
+    }
+
+    /// Value in the default format (`1234.5`), borrowed when already in it
+    pub fn normalize<'a>(&self, value: &'a str) -> Cow<'a, str> {
+        if *self == Self::default() {
+            return Cow::Borrowed(value);
+        }
+        let mut value = value;
+        if self.strip_markers {
+            value = value
+                .trim()
+                .trim_matches(|c: char| c == '%' || CURRENCY_SYMBOLS.contains(&c))
+                .trim();
+        }
+        let is_thousands_separator = |c: char| match self.thousands_separator {
+            Some(' ') => c == ' ' || NO_BREAK_SPACES.contains(&c),
+            Some(separator) => c == separator,
+            None => false,
+        };
+        if self.decimal_separator == '.' && !value.chars().any(is_thousands_separator) {
+            return Cow::Borrowed(value);
+        }
+        // Misplaced thousands separators are kept so that the value does not parse
+        let grouped = is_grouped(value, is_thousands_separator);
+        Cow::Owned(
+            value
+                .chars()
+                .filter(|&c| !(grouped && is_thousands_separator(c)))
+                .map(|c| match c {
+                    c if c == self.decimal_separator => '.',
+                    // A dot is not a decimal separator anymore
+                    '.' => ',',
+                    c => c,
+                })
+                .collect(),
+        )
+    }
+
+    pub fn parse(&self, value: &str) -> Result<f64, ParseFloatError> {
+        self.normalize(value).parse::<f64>()
+    }
+}
+
+/// Whether the thousands separators of the value, if any, are all between groups of 3 digits of
+/// the integer part (`1,234,567.8` but not `1,5` or `12,34`)
+fn is_grouped(value: &str, is_thousands_separator: impl Fn(char) -> bool) -> bool {
+    let is_digits =
+        |group: &str, count| group.len() == count && group.chars().all(|c| c.is_ascii_digit());
+    let mut groups = value.split(&is_thousands_separator);
+    let first = groups.next().unwrap_or_default();
+    let first = first.trim_start_matches(['-', '+']);
+    let Some(last) = groups.next_back() else {
+        return true;
+    };
+    (1..=3).any(|count| is_digits(first, count))
+        && groups.all(|group| is_digits(group, 3))
+        && last.get(..3).is_some_and(|group| is_digits(group, 3))
+        && !last[3..].starts_with(|c: char| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default() {
+        let number_format = NumberFormat::default();
+        assert_eq!(number_format.parse("1234.5"), Ok(1234.5));
+        assert!(number_format.parse("1,234.5").is_err());
+        assert!(matches!(number_format.normalize("12"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_locales() {
+        let en = NumberFormat::from_locale("en-US").unwrap();
+        assert_eq!(en.parse("1,234.56"), Ok(1234.56));
+        let de = NumberFormat::from_locale("de_DE").unwrap();
+        assert_eq!(de.parse("1.234,56"), Ok(1234.56));
+        assert!(de.parse("1,2,3").is_err());
+        assert!(de.parse("1.5").is_err());
+        assert_eq!(de.parse("-12.345.678"), Ok(-12345678.0));
+        assert!(en.parse("1,5").is_err());
+        assert!(en.parse("12,34").is_err());
+        assert!(en.parse("1,2345").is_err());
+        assert!(en.parse("1234,567").is_err());
+        assert!(en.parse("1.234,567").is_err());
+        assert_eq!(en.parse("12,345.6"), Ok(12345.6));
+        let fr = NumberFormat::from_locale("fr").unwrap();
+        assert_eq!(fr.parse("1\u{202f}234,5"), Ok(1234.5));
+        let ch = NumberFormat::from_locale("de-CH").unwrap();
+        assert_eq!(ch.parse("1'234.5"), Ok(1234.5));
+        assert_eq!(NumberFormat::from_locale("xx"), None);
+    }
+
+    #[test]
+    fn test_strip_markers() {
+        let number_format = NumberFormat {
+            strip_markers: true,
+            ..NumberFormat::from_locale("de").unwrap()
+        };
+        assert_eq!(number_format.parse("1.234,50 €"), Ok(1234.5));
+        assert_eq!(number_format.parse("12,5%"), Ok(12.5));
+        assert_eq!(number_format.parse("$ 3"), Ok(3.0));
+    }
+}
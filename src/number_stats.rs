@@ -1,11 +1,45 @@
-use stats::{MinMax, OnlineStats};
+use crate::histogram::{Histogram, HistogramBins};
+use crate::moments::Moments;
+use crate::t_digest::TDigest;
+use crate::value_sample::ValueSample;
+use stats::{Commute, MinMax};
+
+/// Neumaier compensated sum: the rounding errors are kept apart, so that adding the values in
+/// chunks and merging gives the same sum as adding them in line order
+#[derive(Clone, Copy, Debug, Default)]
+struct CompensatedSum {
+    sum: f64,
+    compensation: f64,
+}
+
+impl CompensatedSum {
+    fn add(&mut self, num: f64) {
+        let sum = self.sum + num;
+        self.compensation += if self.sum.abs() >= num.abs() {
+            (self.sum - sum) + num
+        } else {
+            (num - sum) + self.sum
+        };
+        self.sum = sum;
+    }
+    fn merge(&mut self, other: &Self) {
+        self.add(other.sum);
+        self.compensation += other.compensation;
+    }
+    fn value(&self) -> f64 {
+        self.sum + self.compensation
+    }
+}
 
 pub struct NumberStats {
     empty_count: usize,
     error_count: usize,
-    online_stats: OnlineStats,
+    moments: Moments,
     min_max: MinMax<f64>,
-    sum: f64,
+    sum: CompensatedSum,
+    t_digest: TDigest,
+    error_examples: Option<ValueSample>,
+    histogram: Option<Histogram>,
 }
 
 impl NumberStats {
@@ -13,15 +47,56 @@ impl NumberStats {
         Self {
             empty_count: 0,
             error_count: 0,
-            online_stats: OnlineStats::new(),
+            moments: Moments::new(),
             min_max: MinMax::new(),
-            sum: 0.0,
+            sum: CompensatedSum::default(),
+            t_digest: TDigest::new(),
+            error_examples: None,
+            histogram: None,
+        }
+    }
+    /// Also keep the first `size` and a random sample of `size` values that are not numbers (no
+    /// examples for 0)
+    pub fn with_error_examples(size: usize) -> Self {
+        Self {
+            error_examples: (size > 0).then(|| ValueSample::new(size)),
+            ..Self::new()
         }
     }
+    /// Also count the values in histogram bins
+    pub fn with_histogram(mut self, bins: HistogramBins) -> Self {
+        self.histogram = Some(Histogram::new(bins));
+        self
+    }
     pub fn add(&mut self, num: f64) {
-        self.online_stats.add(num);
+        self.moments.add(num);
         self.min_max.add(num);
-        self.sum += num;
+        self.sum.add(num);
+        self.t_digest.add(num);
+        if let Some(histogram) = self.histogram.as_mut() {
+            histogram.add(num);
+        }
+    }
+    /// Combine with stats of another part of the input, as if all values were added to this one
+    pub fn merge(&mut self, other: &Self) {
+        self.empty_count += other.empty_count;
+        self.error_count += other.error_count;
+        self.moments.merge(&other.moments);
+        self.min_max.merge(other.min_max.clone());
+        self.sum.merge(&other.sum);
+        self.t_digest.merge(&other.t_digest);
+        match (self.error_examples.as_mut(), other.error_examples.as_ref()) {
+            (Some(error_examples), Some(other_error_examples)) => {
+                error_examples.merge(other_error_examples)
+            }
+            (None, Some(_)) => self.error_examples = other.error_examples.clone(),
+            _ => {}
+        }
+        match (self.histogram.as_mut(), other.histogram.as_ref()) {
+            (Some(histogram), Some(other_histogram)) => histogram.merge(other_histogram),
+            (None, Some(_)) => self.histogram = other.histogram.clone(),
+            _ => {}
+        }
     }
     pub fn add_empty(&mut self) {
         self.empty_count += 1;
@@ -29,15 +104,19 @@ impl NumberStats {
     pub fn add_error(&mut self) {
         self.error_count += 1;
     }
-    #[allow(dead_code)]
+    /// Count an error for a value that is not a number, keeping it as an example
+    pub fn add_invalid(&mut self, value: &str) {
+        self.add_error();
+        if let Some(error_examples) = self.error_examples.as_mut() {
+            error_examples.add(value.to_string());
+        }
+    }
     pub fn count(&self) -> usize {
-        self.min_max.len()
+        self.moments.count()
     }
-    #[allow(dead_code)]
     pub fn empty_count(&self) -> usize {
         self.empty_count
     }
-    #[allow(dead_code)]
     pub fn error_count(&self) -> usize {
         self.error_count
     }
@@ -48,13 +127,66 @@ impl NumberStats {
         self.min_max.max().copied()
     }
     pub fn mean(&self) -> f64 {
-        self.online_stats.mean()
+        self.moments.mean()
     }
+    /// Population standard deviation
     pub fn stddev(&self) -> f64 {
-        self.online_stats.stddev()
+        self.variance(false).sqrt()
+    }
+    /// Population variance, or sample variance (n - 1 denominator) when `sample`
+    pub fn variance(&self, sample: bool) -> f64 {
+        self.moments.variance(sample)
+    }
+    pub fn skewness(&self) -> Option<f64> {
+        self.moments.skewness()
+    }
+    /// Excess kurtosis
+    pub fn kurtosis(&self) -> Option<f64> {
+        self.moments.kurtosis()
+    }
+    /// Standard error of the mean
+    pub fn std_error(&self) -> Option<f64> {
+        self.moments.std_error()
+    }
+    /// Confidence interval of the mean at the given level (0-100)
+    pub fn mean_interval(&self, confidence: f64) -> Option<(f64, f64)> {
+        self.moments.mean_interval(confidence)
     }
     pub fn sum(&self) -> f64 {
-        self.sum
+        self.sum.value()
+    }
+    /// Approximate quantile (0.0..=1.0) from the t-digest sketch
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        self.t_digest.quantile(q)
+    }
+    pub fn median(&self) -> Option<f64> {
+        self.quantile(0.5)
+    }
+    /// Examples of the values that are not numbers
+    pub fn error_examples(&self) -> Vec<String> {
+        self.error_examples
+            .as_ref()
+            .map(ValueSample::values)
+            .unwrap_or_default()
+    }
+    /// Lower bound, upper bound and count of the histogram bins, when requested and there are
+    /// numbers
+    pub fn histogram(&self) -> Vec<(Option<f64>, Option<f64>, usize)> {
+        self.histogram
+            .as_ref()
+            .filter(|_| self.count() > 0)
+            .map(|histogram| histogram.bins(&self.t_digest))
+            .unwrap_or_default()
+    }
+    /// Interquartile range (p75 - p25)
+    pub fn iqr(&self) -> Option<f64> {
+        Some(self.quantile(0.75)? - self.quantile(0.25)?)
+    }
+}
+
+impl Default for NumberStats {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -73,6 +205,8 @@ mod tests {
         assert_eq!(stats.sum(), 0.0);
         assert_eq!(stats.mean(), 0.0);
         assert_eq!(stats.stddev(), 0.0);
+        assert_eq!(stats.median(), None);
+        assert_eq!(stats.iqr(), None);
     }
 
     #[test]
@@ -86,6 +220,105 @@ mod tests {
         assert_eq!(stats.sum(), 3.0);
         assert_eq!(stats.mean(), 1.5);
         assert!((stats.stddev() - 0.5).abs() < f64::EPSILON);
+        assert!((stats.variance(true) - 0.5).abs() < f64::EPSILON);
+        assert_eq!(stats.skewness(), Some(0.0));
+        assert!(stats.mean_interval(95.0).is_some());
+    }
+
+    #[test]
+    fn test_quantiles() {
+        let mut stats = NumberStats::new();
+        for num in 1..=5 {
+            stats.add(num as f64);
+        }
+        assert_eq!(stats.median(), Some(3.0));
+        assert_eq!(stats.quantile(0.0), Some(1.0));
+        assert_eq!(stats.quantile(1.0), Some(5.0));
+        assert_eq!(stats.iqr(), Some(2.5));
+    }
+
+    #[test]
+    fn test_merge() {
+        let values = [3.0, 1.0, 4.0, 1.0, 5.0, 9.0, 2.0, 6.0];
+        let mut all = NumberStats::new();
+        let (mut left, mut right) = (NumberStats::new(), NumberStats::new());
+        for (index, &value) in values.iter().enumerate() {
+            all.add(value);
+            if index < 3 {
+                left.add(value);
+            } else {
+                right.add(value);
+            }
+        }
+        all.add_empty();
+        left.add_empty();
+        all.add_error();
+        right.add_error();
+
+        left.merge(&right);
+        assert_eq!(left.count(), all.count());
+        assert_eq!(left.empty_count(), all.empty_count());
+        assert_eq!(left.error_count(), all.error_count());
+        assert_eq!(left.min(), all.min());
+        assert_eq!(left.max(), all.max());
+        assert_eq!(left.sum(), all.sum());
+        assert!((left.mean() - all.mean()).abs() < 1e-12);
+        assert!((left.stddev() - all.stddev()).abs() < 1e-12);
+        assert!((left.kurtosis().unwrap() - all.kurtosis().unwrap()).abs() < 1e-12);
+        assert_eq!(left.median(), all.median());
+        assert_eq!(left.iqr(), all.iqr());
+    }
+
+    #[test]
+    fn test_merge_sum() {
+        let values: Vec<f64> = (0..10000)
+            .map(|index| 1e4 + index as f64 * 0.37 + 0.1)
+            .collect();
+        let mut all = NumberStats::new();
+        let mut merged = NumberStats::new();
+        for chunk in values.chunks(777) {
+            let mut part = NumberStats::new();
+            for &value in chunk {
+                all.add(value);
+                part.add(value);
+            }
+            merged.merge(&part);
+        }
+        assert_eq!(merged.sum(), all.sum());
+        let naive = values.iter().sum::<f64>();
+        let naive_chunks = values
+            .chunks(777)
+            .map(|chunk| chunk.iter().sum::<f64>())
+            .sum::<f64>();
+        assert_ne!(naive, naive_chunks);
+    }
+
+    #[test]
+    fn test_error_examples() {
+        let mut stats = NumberStats::with_error_examples(1);
+        stats.add_invalid("x");
+        stats.add_invalid("y");
+        stats.add_error();
+        assert_eq!(stats.error_count(), 3);
+        assert_eq!(stats.error_examples(), vec!["x", "y"]);
+        assert!(NumberStats::new().error_examples().is_empty());
+
+        let mut empty = NumberStats::new();
+        empty.merge(&stats);
+        assert_eq!(empty.error_examples(), vec!["x", "y"]);
+    }
+
+    #[test]
+    fn test_merge_empty() {
+        let mut stats = NumberStats::new();
+        stats.merge(&NumberStats::new());
+        assert_eq!(stats.count(), 0);
+        assert_eq!(stats.mean(), 0.0);
+        stats.add(2.0);
+        let mut empty = NumberStats::new();
+        empty.merge(&stats);
+        assert_eq!(empty.mean(), 2.0);
+        assert_eq!(empty.median(), Some(2.0));
     }
 
     #[test]
@@ -0,0 +1,12 @@
+/// How the stats are written to stdout
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human readable table
+    Table,
+    /// Delimited output with the given delimiter
+    Csv(char),
+    /// A single JSON document with the rows of typed stats, then the sections
+    Json,
+    /// One JSON object of typed stats per row
+    Ndjson,
+}
@@ -0,0 +1,347 @@
+use crate::count_stats::CountStats;
+use crate::date_stats::DateStats;
+use crate::mode::Mode;
+use crate::number_stats::NumberStats;
+use crate::ragged_stats::RaggedStats;
+use crate::reject::Reject;
+use crate::stat::Stat;
+use crate::string_stats::StringStats;
+use crate::type_stats::{TypeStats, ValueType};
+use serde::Serialize;
+
+/// Structured result of a profiler run, with one row per profiled group or column
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Profile {
+    pub mode: Mode,
+    /// Statistics of the table and csv output, in order
+    #[serde(skip)]
+    pub stats: Vec<Stat>,
+    pub rows: Vec<ProfileRow>,
+    /// Rows with fewer or more fields than the headers (csv mode)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ragged_rows: Option<RaggedSummary>,
+    /// Erroring records, when requested, up to the maximum per reason and column
+    #[serde(skip)]
+    pub rejects: Vec<Reject>,
+    /// Number of erroring records over the maximum per reason and column
+    #[serde(skip)]
+    pub dropped_rejects: usize,
+    /// Correlations of the pairs of number columns, when requested (csv mode)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub correlations: Vec<Correlation>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Correlation {
+    pub columns: [String; 2],
+    /// Number of rows where both values are numbers
+    pub count: usize,
+    pub pearson: Option<f64>,
+    /// Exact up to 10000 rows, estimated on a uniform sample of 10000 rows beyond
+    pub spearman: Option<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RaggedSummary {
+    pub short_rows: usize,
+    pub long_rows: usize,
+    /// Line numbers of the first short or long rows
+    pub first_lines: Vec<usize>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ProfileRow {
+    /// Group keys (group modes), empty otherwise
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub group: Vec<String>,
+    /// Column header (csv mode)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<String>,
+    /// Type inferred from the non-empty values (csv mode)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inferred_type: Option<TypeSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub string: Option<StringSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub number: Option<NumberSummary>,
+    /// Stats on the values recognized as dates (date mode, csv mode columns with dates)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<DateSummary>,
+    /// Frequency table of the values (count modes)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub counts: Option<CountSummary>,
+    /// Stats on the length of the (non-empty) string values
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub length: Option<NumberSummary>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TypeSummary {
+    #[serde(rename = "type")]
+    pub value_type: ValueType,
+    /// Number of non-empty values conforming to the type
+    pub conforming: usize,
+    /// Share of the non-empty values conforming to the type
+    pub share: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct NumberSummary {
+    pub count: usize,
+    pub empty: usize,
+    pub error: usize,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub sum: f64,
+    pub mean: f64,
+    /// Population or (when requested) sample standard deviation
+    pub stddev: f64,
+    /// Population or (when requested) sample variance
+    pub variance: f64,
+    pub skewness: Option<f64>,
+    /// Excess kurtosis (0 for a normal distribution)
+    pub kurtosis: Option<f64>,
+    /// Coefficient of variation (stddev / mean)
+    pub cv: Option<f64>,
+    /// Standard error of the mean
+    pub std_error: Option<f64>,
+    pub mean_interval: Option<MeanInterval>,
+    pub median: Option<f64>,
+    pub iqr: Option<f64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub percentiles: Vec<Percentile>,
+    /// Examples of the values that are not numbers, when requested
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub error_examples: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub histogram: Vec<HistogramBin>,
+}
+
+/// Confidence interval of the mean, from the Student t distribution
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MeanInterval {
+    /// Confidence level (0-100)
+    pub confidence: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct HistogramBin {
+    /// Null for the bin of the values below the fixed bins or of the non-positive values
+    pub lower: Option<f64>,
+    /// Null for the bin of the values above the fixed bins
+    pub upper: Option<f64>,
+    pub count: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Percentile {
+    pub percentile: f64,
+    pub value: Option<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DateSummary {
+    pub count: usize,
+    pub empty: usize,
+    pub error: usize,
+    /// Earliest datetime in ISO 8601, converted to UTC for values with an offset
+    pub min: Option<String>,
+    pub max: Option<String>,
+    /// Seconds between the min and the max
+    pub span: Option<f64>,
+    /// Number of values per recognized format, most frequent first
+    pub formats: Vec<FormatCount>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct FormatCount {
+    pub format: String,
+    pub count: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct StringSummary {
+    pub count: usize,
+    pub empty: usize,
+    pub error: usize,
+    pub cardinality: usize,
+    pub min: Option<String>,
+    pub max: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub top: Vec<TopValue>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CountSummary {
+    pub total: usize,
+    pub distinct: usize,
+    /// Most frequent values first, limited to the requested top and min count
+    pub values: Vec<ValueCount>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ValueCount {
+    pub value: String,
+    pub count: usize,
+    /// Share of the total
+    pub share: f64,
+    /// Share of the total of this and the more frequent values
+    pub cumulative_share: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TopValue {
+    pub value: String,
+    pub count: usize,
+    /// Share of the non-empty count
+    pub share: f64,
+}
+
+impl RaggedSummary {
+    pub fn new(ragged_stats: &RaggedStats) -> Self {
+        Self {
+            short_rows: ragged_stats.short_count(),
+            long_rows: ragged_stats.long_count(),
+            first_lines: ragged_stats.first_lines().to_vec(),
+        }
+    }
+}
+
+impl TypeSummary {
+    pub fn new(type_stats: &TypeStats) -> Self {
+        let value_type = type_stats.inferred_type();
+        Self {
+            value_type,
+            conforming: type_stats.conforming(value_type),
+            share: type_stats.share(),
+        }
+    }
+}
+
+impl NumberSummary {
+    /// Summary with the given percentiles (0-100), the sample instead of the population standard
+    /// deviation when `sample`, and a confidence interval of the mean at the `confidence` level
+    pub fn new(
+        number_stats: &NumberStats,
+        percentiles: &[f64],
+        sample: bool,
+        confidence: f64,
+    ) -> Self {
+        let variance = number_stats.variance(sample);
+        let stddev = variance.sqrt();
+        let mean = number_stats.mean();
+        Self {
+            count: number_stats.count(),
+            empty: number_stats.empty_count(),
+            error: number_stats.error_count(),
+            min: number_stats.min(),
+            max: number_stats.max(),
+            sum: number_stats.sum(),
+            mean,
+            stddev,
+            variance,
+            skewness: number_stats.skewness(),
+            kurtosis: number_stats.kurtosis(),
+            cv: (number_stats.count() > 0 && mean != 0.0).then(|| stddev / mean),
+            std_error: number_stats.std_error(),
+            mean_interval: number_stats
+                .mean_interval(confidence)
+                .map(|(lower, upper)| MeanInterval {
+                    confidence,
+                    lower,
+                    upper,
+                }),
+            median: number_stats.median(),
+            iqr: number_stats.iqr(),
+            percentiles: percentiles
+                .iter()
+                .map(|&percentile| Percentile {
+                    percentile,
+                    value: number_stats.quantile(percentile / 100.0),
+                })
+                .collect(),
+            error_examples: number_stats.error_examples(),
+            histogram: number_stats
+                .histogram()
+                .into_iter()
+                .map(|(lower, upper, count)| HistogramBin {
+                    lower,
+                    upper,
+                    count,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl DateSummary {
+    pub fn new(date_stats: &DateStats) -> Self {
+        let format =
+            |datetime: chrono::NaiveDateTime| datetime.format("%Y-%m-%dT%H:%M:%S%.f").to_string();
+        Self {
+            count: date_stats.count(),
+            empty: date_stats.empty_count(),
+            error: date_stats.error_count(),
+            min: date_stats.min().map(format),
+            max: date_stats.max().map(format),
+            span: date_stats.span(),
+            formats: date_stats
+                .format_counts()
+                .into_iter()
+                .map(|(format, count)| FormatCount { format, count })
+                .collect(),
+        }
+    }
+}
+
+impl CountSummary {
+    /// Summary of the `top` most frequent values (all for 0) occurring at least `min_count` times
+    pub fn new(count_stats: &CountStats, top: usize, min_count: usize) -> Self {
+        let total = count_stats.total();
+        let mut cumulative = 0;
+        let values = count_stats
+            .counts()
+            .into_iter()
+            .take_while(|(_value, count)| *count >= min_count)
+            .take(if top == 0 { usize::MAX } else { top })
+            .map(|(value, count)| {
+                cumulative += count;
+                ValueCount {
+                    value,
+                    count,
+                    share: count as f64 / total as f64,
+                    cumulative_share: cumulative as f64 / total as f64,
+                }
+            })
+            .collect();
+        Self {
+            total,
+            distinct: count_stats.distinct(),
+            values,
+        }
+    }
+}
+
+impl StringSummary {
+    pub fn new(string_stats: &StringStats) -> Self {
+        Self {
+            count: string_stats.count(),
+            empty: string_stats.empty_count(),
+            error: string_stats.error_count(),
+            cardinality: string_stats.cardinality(),
+            min: string_stats.min(),
+            max: string_stats.max(),
+            top: string_stats
+                .top()
+                .into_iter()
+                .map(|(value, count, share)| TopValue {
+                    value,
+                    count,
+                    share,
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,1414 @@
+use crate::chunk;
+use crate::date_stats::DateParser;
+use crate::histogram::HistogramBins;
+use crate::mode::{self, Mode};
+use crate::number_bin::{quantile_edges, Binning, GroupBins, NumberBin};
+use crate::number_format::NumberFormat;
+use crate::number_stats::NumberStats;
+use crate::profile::{NumberSummary, Profile, ProfileRow};
+use crate::record_reader::{Dialect, RecordReader};
+use crate::reject::MAX_REJECTS;
+use crate::stat::Stat;
+use crate::string_stats::StringStats;
+use crate::t_digest::TDigest;
+use crate::time_bucket::{GroupBuckets, TimeBucket};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+type Records<'a> = Box<dyn Iterator<Item = io::Result<(usize, Vec<String>)>> + 'a>;
+
+/// Builder style entry point of the stats engine.
+///
+/// ```
+/// use analyze_data::{Mode, Profiler};
+///
+/// let profile = Profiler::new(Mode::Number)
+///     .percentiles(vec![95.0])
+///     .profile_reader("1\n2\n3\n".as_bytes())
+///     .unwrap();
+/// assert_eq!(profile.rows[0].number.as_ref().unwrap().median, Some(2.0));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Profiler {
+    pub(crate) mode: Mode,
+    pub(crate) dialect: Dialect,
+    pub(crate) zero_as_empty: bool,
+    pub(crate) null_values: Vec<String>,
+    pub(crate) null_values_ignore_case: bool,
+    pub(crate) percentiles: Vec<f64>,
+    pub(crate) top: usize,
+    pub(crate) min_count: usize,
+    pub(crate) threads: usize,
+    pub(crate) group_by: Vec<String>,
+    pub(crate) columns: Vec<String>,
+    pub(crate) exclude_columns: Vec<String>,
+    pub(crate) no_header: bool,
+    pub(crate) header_names: Vec<String>,
+    pub(crate) missing_as_empty: bool,
+    pub(crate) rejects: bool,
+    pub(crate) max_rejects: usize,
+    pub(crate) error_examples: usize,
+    pub(crate) number_format: NumberFormat,
+    pub(crate) date_formats: Vec<String>,
+    pub(crate) time_buckets: Vec<TimeBucket>,
+    pub(crate) bins: Vec<NumberBin>,
+    pub(crate) histogram: Option<HistogramBins>,
+    pub(crate) correlations: bool,
+    pub(crate) moments: bool,
+    pub(crate) sample_stddev: bool,
+    pub(crate) confidence: f64,
+    pub(crate) stats: Vec<Stat>,
+    /// Edges of the quantile bins by group position, once estimated
+    pub(crate) quantile_edges: Option<HashMap<usize, Vec<f64>>>,
+}
+
+impl Profiler {
+    pub fn new(mode: Mode) -> Self {
+        Self {
+            mode,
+            dialect: Dialect::default(),
+            zero_as_empty: false,
+            null_values: Vec::new(),
+            null_values_ignore_case: false,
+            percentiles: Vec::new(),
+            top: 0,
+            min_count: 0,
+            threads: 1,
+            group_by: Vec::new(),
+            columns: Vec::new(),
+            exclude_columns: Vec::new(),
+            no_header: false,
+            header_names: Vec::new(),
+            missing_as_empty: false,
+            rejects: false,
+            max_rejects: MAX_REJECTS,
+            error_examples: 0,
+            number_format: NumberFormat::default(),
+            date_formats: Vec::new(),
+            time_buckets: Vec::new(),
+            bins: Vec::new(),
+            histogram: None,
+            correlations: false,
+            moments: false,
+            sample_stddev: false,
+            confidence: 95.0,
+            stats: Vec::new(),
+            quantile_edges: None,
+        }
+    }
+    /// Input delimiter, quote and escape characters (csv and group modes)
+    pub fn dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+    /// Count zeros as empty when parsing numbers
+    pub fn zero_as_empty(mut self, zero_as_empty: bool) -> Self {
+        self.zero_as_empty = zero_as_empty;
+        self
+    }
+    /// Values counted as empty, next to the empty string (e.g. `NA`, `NULL`, `\N`)
+    pub fn null_values(mut self, null_values: Vec<String>) -> Self {
+        self.null_values = null_values;
+        self
+    }
+    /// Match the null values ignoring ASCII case
+    pub fn null_values_ignore_case(mut self, null_values_ignore_case: bool) -> Self {
+        self.null_values_ignore_case = null_values_ignore_case;
+        self
+    }
+    /// Decimal and thousands separators of the numbers (number, group-number and csv modes)
+    pub fn number_format(mut self, number_format: NumberFormat) -> Self {
+        self.number_format = number_format;
+        self
+    }
+    /// `strftime` formats of the dates, tried before ISO 8601, RFC 3339 and (date mode) epoch
+    /// seconds or millis. `%s` recognizes epoch seconds in csv mode.
+    pub fn date_formats(mut self, date_formats: Vec<String>) -> Self {
+        self.date_formats = date_formats;
+        self
+    }
+    /// Percentiles (0-100) to report next to the median and IQR
+    pub fn percentiles(mut self, percentiles: Vec<f64>) -> Self {
+        self.percentiles = percentiles;
+        self
+    }
+    /// Histogram bins to report for the number stats (number, group-number and csv modes)
+    pub fn histogram(mut self, histogram: Option<HistogramBins>) -> Self {
+        self.histogram = histogram;
+        self
+    }
+    /// Report the Pearson and Spearman correlations of the pairs of number columns (csv mode)
+    pub fn correlations(mut self, correlations: bool) -> Self {
+        self.correlations = correlations;
+        self
+    }
+    /// Output the variance, skewness, excess kurtosis, coefficient of variation, standard error
+    /// and confidence interval of the mean next to the number stats (they are always in the
+    /// profile)
+    pub fn moments(mut self, moments: bool) -> Self {
+        self.moments = moments;
+        self
+    }
+    /// Use the sample (n - 1 denominator) instead of the population standard deviation and
+    /// variance
+    pub fn sample_stddev(mut self, sample_stddev: bool) -> Self {
+        self.sample_stddev = sample_stddev;
+        self
+    }
+    /// Level (0-100) of the confidence interval of the mean
+    pub fn confidence(mut self, confidence: f64) -> Self {
+        self.confidence = confidence;
+        self
+    }
+    /// Statistics of the table and csv output in order (the defaults of the mode for none). Their
+    /// percentiles and most frequent values are computed next to the requested ones.
+    pub fn stats(mut self, stats: Vec<Stat>) -> Self {
+        self.stats = stats;
+        self
+    }
+    /// Number of most frequent values to report for string stats, and of values in the count
+    /// modes (all for 0)
+    pub fn top(mut self, top: usize) -> Self {
+        self.top = top;
+        self
+    }
+    /// Minimum count of the values to report in the count modes
+    pub fn min_count(mut self, min_count: usize) -> Self {
+        self.min_count = min_count;
+        self
+    }
+
+    /// Number of threads for [`Profiler::profile_path`], which splits the file into chunks of
+    /// whole records
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Columns (header names or 1-based indices) to group the csv mode stats by
+    pub fn group_by(mut self, group_by: Vec<String>) -> Self {
+        self.group_by = group_by;
+        self
+    }
+
+    /// Timestamp columns to truncate to an hour, day, week or month and group by, output in
+    /// chronological order. In the group modes the columns are 1-based indices of the group
+    /// fields, in csv mode they are added to the group by columns.
+    pub fn time_buckets(mut self, time_buckets: Vec<TimeBucket>) -> Self {
+        self.time_buckets = time_buckets;
+        self
+    }
+
+    /// Number columns (1-based indices of the group fields) to bin into ranges in the group
+    /// modes, output in range order
+    pub fn bins(mut self, bins: Vec<NumberBin>) -> Self {
+        self.bins = bins;
+        self
+    }
+
+    /// Columns to profile in csv mode (all when empty), referenced by header name, 1-based index,
+    /// range of indices (`2-5`, `3-`, `-4`) or glob pattern on the header names
+    pub fn columns(mut self, columns: Vec<String>) -> Self {
+        self.columns = columns;
+        self
+    }
+    /// Columns not to profile in csv mode, referenced like [`Profiler::columns`]
+    pub fn exclude_columns(mut self, exclude_columns: Vec<String>) -> Self {
+        self.exclude_columns = exclude_columns;
+        self
+    }
+
+    /// The csv mode input has no header row, columns are named `col1..colN`
+    pub fn no_header(mut self, no_header: bool) -> Self {
+        self.no_header = no_header;
+        self
+    }
+    /// Column names for csv mode input without a header row, extra columns are named `colN`
+    pub fn header_names(mut self, header_names: Vec<String>) -> Self {
+        self.header_names = header_names;
+        self
+    }
+
+    /// Count the missing trailing fields of short rows as empty (csv mode)
+    pub fn missing_as_empty(mut self, missing_as_empty: bool) -> Self {
+        self.missing_as_empty = missing_as_empty;
+        self
+    }
+
+    /// Collect the erroring records in [`Profile::rejects`]
+    pub fn rejects(mut self, rejects: bool) -> Self {
+        self.rejects = rejects;
+        self
+    }
+    /// Maximum number of erroring records to collect per reason and column, the first ones in
+    /// line order (default 1000)
+    pub fn max_rejects(mut self, max_rejects: usize) -> Self {
+        self.max_rejects = max_rejects;
+        self
+    }
+
+    /// Number of first and of randomly sampled values that are not numbers to keep as examples
+    pub fn error_examples(mut self, error_examples: usize) -> Self {
+        self.error_examples = error_examples;
+        self
+    }
+
+    /// Profile a stream. The number, string, date and count modes take each line as a single value, the other
+    /// modes parse the lines into records using the dialect. With quantile bins, the stream is
+    /// copied to a temporary file for the two passes over it.
+    pub fn profile_reader<R: BufRead>(&self, mut buf_reader: R) -> io::Result<Profile> {
+        if self.needs_quantile_edges() {
+            let path = spool_path();
+            let profile = File::create_new(&path)
+                .and_then(|mut file| io::copy(&mut buf_reader, &mut file))
+                .and_then(|_| self.profile_path(&path));
+            let _ = std::fs::remove_file(&path);
+            return profile;
+        }
+        itertools::process_results(self.records(buf_reader), |records| {
+            self.profile_numbered_records(records)
+        })?
+    }
+
+    /// Profile a file, in parallel chunks of records when multiple threads are configured. The
+    /// per chunk stats are merged, so the result matches [`Profiler::profile_reader`] up to the
+    /// last bits of the floating point moments (mean, standard deviation...) and correlations,
+    /// while quantiles and top values are approximations either way.
+    pub fn profile_path<P: AsRef<Path>>(&self, path: P) -> io::Result<Profile> {
+        let path = path.as_ref();
+        if self.needs_quantile_edges() {
+            let records = self.records(BufReader::new(File::open(path)?));
+            return self
+                .with_quantile_edges(records.map(|record| record.map(|(_line, record)| record)))?
+                .profile_path(path);
+        }
+        if self.threads <= 1 {
+            return self.profile_reader(BufReader::new(File::open(path)?));
+        }
+
+        let dialect = self.record_dialect();
+        let (headers, start) = match (self.mode, dialect) {
+            (Mode::Csv, Some(dialect)) => {
+                let first_record = RecordReader::new(BufReader::new(File::open(path)?), dialect)
+                    .next()
+                    .transpose()?
+                    .unwrap_or_default();
+                if self.has_header() {
+                    (
+                        Some(first_record),
+                        chunk::record_end(path, 0, false, dialect)?,
+                    )
+                } else {
+                    (Some(self.synthetic_headers(first_record.len())), 0)
+                }
+            }
+            _ => (None, 0),
+        };
+        let chunks = chunk::line_aligned_chunks(path, start, self.threads, dialect)?;
+        let first_lines = chunk::first_lines(path, &chunks)?;
+        // Every chunk starts with the headers
+        let chunk_profiler = self.clone().no_header(false).header_names(Vec::new());
+
+        let profile = match self.mode {
+            Mode::Number => mode::number::profile(
+                self.build_chunks(
+                    path,
+                    &chunks,
+                    &first_lines,
+                    &headers,
+                    |records| Ok(mode::number::build_data(records, &chunk_profiler)),
+                    mode::number::merge_data,
+                )?,
+                self,
+            ),
+            Mode::String => mode::string::profile(
+                self.build_chunks(
+                    path,
+                    &chunks,
+                    &first_lines,
+                    &headers,
+                    |records| Ok(mode::string::build_data(records, &chunk_profiler)),
+                    mode::string::merge_data,
+                )?,
+                self,
+            ),
+            Mode::Date => mode::date::profile(
+                self.build_chunks(
+                    path,
+                    &chunks,
+                    &first_lines,
+                    &headers,
+                    |records| Ok(mode::date::build_data(records, &chunk_profiler)),
+                    mode::date::merge_data,
+                )?,
+                self,
+            ),
+            Mode::Count => mode::count::profile(
+                self.build_chunks(
+                    path,
+                    &chunks,
+                    &first_lines,
+                    &headers,
+                    |records| Ok(mode::count::build_data(records, &chunk_profiler)),
+                    mode::count::merge_data,
+                )?,
+                self,
+            ),
+            Mode::GroupNumber => mode::group_number::profile(
+                self.build_chunks(
+                    path,
+                    &chunks,
+                    &first_lines,
+                    &headers,
+                    |records| mode::group_number::build_data(records, &chunk_profiler),
+                    mode::group_number::merge_data,
+                )?,
+                self,
+            )?,
+            Mode::GroupString => mode::group_string::profile(
+                self.build_chunks(
+                    path,
+                    &chunks,
+                    &first_lines,
+                    &headers,
+                    |records| mode::group_string::build_data(records, &chunk_profiler),
+                    mode::group_string::merge_data,
+                )?,
+                self,
+            )?,
+            Mode::GroupCount => mode::group_count::profile(
+                self.build_chunks(
+                    path,
+                    &chunks,
+                    &first_lines,
+                    &headers,
+                    |records| mode::group_count::build_data(records, &chunk_profiler),
+                    mode::group_count::merge_data,
+                )?,
+                self,
+            )?,
+            Mode::Csv => mode::csv::profile(
+                self.build_chunks(
+                    path,
+                    &chunks,
+                    &first_lines,
+                    &headers,
+                    |records| mode::csv::build_data(records, &chunk_profiler),
+                    mode::csv::merge_data,
+                )?,
+                self,
+            ),
+        };
+        Ok(profile)
+    }
+
+    /// Profile already parsed records. The value is the last field of each record, the
+    /// preceding fields form the group in the group modes. In csv mode the first record holds the
+    /// headers. Options referencing unknown columns fail with an `InvalidInput` error. With
+    /// quantile bins, the records are collected for the two passes over them.
+    pub fn profile_records<I: IntoIterator<Item = Vec<String>>>(
+        &self,
+        records: I,
+    ) -> io::Result<Profile> {
+        if self.needs_quantile_edges() {
+            let records: Vec<Vec<String>> = records.into_iter().collect();
+            return self
+                .with_quantile_edges(records.iter().cloned().map(Ok))?
+                .profile_records(records);
+        }
+        self.profile_numbered_records(
+            records
+                .into_iter()
+                .enumerate()
+                .map(|(index, record)| (index + 1, record)),
+        )
+    }
+
+    /// Profile records with the line number of their first line
+    fn profile_numbered_records<I: Iterator<Item = (usize, Vec<String>)>>(
+        &self,
+        records: I,
+    ) -> io::Result<Profile> {
+        let profile = match self.mode {
+            Mode::Number => mode::number::profile(mode::number::build_data(records, self), self),
+            Mode::String => mode::string::profile(mode::string::build_data(records, self), self),
+            Mode::Date => mode::date::profile(mode::date::build_data(records, self), self),
+            Mode::Count => mode::count::profile(mode::count::build_data(records, self), self),
+            Mode::GroupNumber => {
+                mode::group_number::profile(mode::group_number::build_data(records, self)?, self)?
+            }
+            Mode::GroupString => {
+                mode::group_string::profile(mode::group_string::build_data(records, self)?, self)?
+            }
+            Mode::GroupCount => {
+                mode::group_count::profile(mode::group_count::build_data(records, self)?, self)?
+            }
+            Mode::Csv => mode::csv::profile(mode::csv::build_data(records, self)?, self),
+        };
+        Ok(profile)
+    }
+
+    /// Whether the first csv mode record holds the headers
+    pub(crate) fn has_header(&self) -> bool {
+        !self.no_header && self.header_names.is_empty()
+    }
+
+    /// Header names for csv mode input without a header row
+    pub(crate) fn synthetic_headers(&self, length: usize) -> Vec<String> {
+        (0..length.max(self.header_names.len()))
+            .map(|index| {
+                self.header_names
+                    .get(index)
+                    .cloned()
+                    .unwrap_or_else(|| format!("col{}", index + 1))
+            })
+            .collect()
+    }
+
+    /// Number stats of the values, with the requested error examples and histogram
+    pub(crate) fn number_stats(&self) -> NumberStats {
+        let number_stats = NumberStats::with_error_examples(self.error_examples);
+        match self.histogram {
+            Some(histogram) => number_stats.with_histogram(histogram),
+            None => number_stats,
+        }
+    }
+
+    /// String stats of the values, keeping the requested most frequent values
+    pub(crate) fn string_stats(&self) -> StringStats {
+        let top = self.stats.iter().filter_map(Stat::top_position).max();
+        StringStats::with_top(self.top.max(top.unwrap_or(0)))
+    }
+
+    /// Summary of number stats, with the requested percentiles, standard deviation and
+    /// confidence level
+    pub(crate) fn number_summary(&self, number_stats: &NumberStats) -> NumberSummary {
+        let mut percentiles = self.percentiles.clone();
+        for percentile in self.stats.iter().filter_map(Stat::percentile) {
+            if !percentiles.contains(&percentile) {
+                percentiles.push(percentile);
+            }
+        }
+        NumberSummary::new(
+            number_stats,
+            &percentiles,
+            self.sample_stddev,
+            self.confidence,
+        )
+    }
+
+    /// Time buckets of the group modes
+    pub(crate) fn group_buckets(&self) -> io::Result<GroupBuckets> {
+        Ok(GroupBuckets::new(
+            self.time_buckets
+                .iter()
+                .map(|time_bucket| {
+                    Ok((
+                        group_position(&time_bucket.column, "time bucket")?,
+                        time_bucket.unit,
+                    ))
+                })
+                .collect::<io::Result<_>>()?,
+            DateParser::new(&self.date_formats, true),
+        ))
+    }
+
+    /// Number bins of the group modes
+    pub(crate) fn group_bins(&self) -> io::Result<GroupBins> {
+        Ok(GroupBins::new(
+            self.bins
+                .iter()
+                .map(|bin| Ok((group_position(&bin.column, "bin")?, bin.binning.clone())))
+                .collect::<io::Result<_>>()?,
+            self.quantile_edges.clone().unwrap_or_default(),
+            self.number_format,
+        ))
+    }
+
+    /// Whether the quantile bins of the group modes still need their edges, estimated in a first
+    /// pass over the input
+    fn needs_quantile_edges(&self) -> bool {
+        matches!(
+            self.mode,
+            Mode::GroupNumber | Mode::GroupString | Mode::GroupCount
+        ) && self.quantile_edges.is_none()
+            && self
+                .bins
+                .iter()
+                .any(|bin| matches!(bin.binning, Binning::Quantiles(_)))
+    }
+
+    /// Profiler with the edges of the quantile bins estimated from the group fields of the records
+    fn with_quantile_edges<I: Iterator<Item = io::Result<Vec<String>>>>(
+        &self,
+        records: I,
+    ) -> io::Result<Self> {
+        let mut t_digests = self
+            .bins
+            .iter()
+            .filter_map(|bin| match bin.binning {
+                Binning::Quantiles(count) => Some(
+                    group_position(&bin.column, "bin")
+                        .map(|position| (position, count, TDigest::new())),
+                ),
+                _ => None,
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        for record in records {
+            let record = record?;
+            // The value is the last field, the group the preceding ones
+            let Some((_value, group)) = record.split_last() else {
+                continue;
+            };
+            for (position, _count, t_digest) in &mut t_digests {
+                if let Some(num) = group
+                    .get(*position)
+                    .filter(|value| !value.is_empty())
+                    .and_then(|value| self.number_format.parse(value).ok())
+                {
+                    t_digest.add(num);
+                }
+            }
+        }
+        Ok(Self {
+            quantile_edges: Some(
+                t_digests
+                    .into_iter()
+                    .map(|(position, count, t_digest)| (position, quantile_edges(&t_digest, count)))
+                    .collect(),
+            ),
+            ..self.clone()
+        })
+    }
+
+    /// Check that the time buckets and bins of the group modes reference one of the group fields
+    /// of the records, given their group keys
+    pub(crate) fn check_group_fields<'a>(
+        &self,
+        groups: impl Iterator<Item = &'a Vec<String>>,
+    ) -> io::Result<()> {
+        let Some(length) = groups.map(Vec::len).max() else {
+            return Ok(());
+        };
+        let references = self
+            .time_buckets
+            .iter()
+            .map(|time_bucket| (&time_bucket.column, "time bucket"))
+            .chain(self.bins.iter().map(|bin| (&bin.column, "bin")));
+        for (column, option) in references {
+            if group_position(column, option)? >= length {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "{} column {} is beyond the group fields ({} at most)",
+                        option, column, length
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the value is empty or one of the null values
+    pub(crate) fn is_null(&self, value: &str) -> bool {
+        value.is_empty()
+            || self.null_values.iter().any(|null_value| {
+                if self.null_values_ignore_case {
+                    null_value.eq_ignore_ascii_case(value)
+                } else {
+                    null_value == value
+                }
+            })
+    }
+
+    /// Profile of the rows, without ragged rows and rejects
+    pub(crate) fn profile(&self, rows: Vec<ProfileRow>) -> Profile {
+        Profile {
+            mode: self.mode,
+            stats: if self.stats.is_empty() {
+                Stat::defaults(self.mode, &self.percentiles, self.top, self.moments)
+            } else {
+                self.stats.clone()
+            },
+            rows,
+            ragged_rows: None,
+            rejects: Vec::new(),
+            dropped_rejects: 0,
+            correlations: Vec::new(),
+        }
+    }
+
+    /// Dialect of the modes parsing the lines into records, None for the modes taking each line
+    /// as a single value
+    fn record_dialect(&self) -> Option<Dialect> {
+        match self.mode {
+            Mode::Number | Mode::String | Mode::Date | Mode::Count => None,
+            Mode::GroupNumber | Mode::GroupString | Mode::GroupCount | Mode::Csv => {
+                Some(self.dialect)
+            }
+        }
+    }
+
+    fn records<'a, R: BufRead + 'a>(&self, buf_reader: R) -> Records<'a> {
+        match self.record_dialect() {
+            None => Box::new(
+                buf_reader
+                    .lines()
+                    .enumerate()
+                    .map(|(index, line)| line.map(|value| (index + 1, vec![value]))),
+            ),
+            Some(dialect) => Box::new(RecordReader::new(buf_reader, dialect).with_line_numbers()),
+        }
+    }
+
+    /// Build data for each chunk in its own thread (with the headers prepended as line 1, if any)
+    /// and merge the results in chunk order
+    fn build_chunks<D, B, M>(
+        &self,
+        path: &Path,
+        chunks: &[(u64, u64)],
+        first_lines: &[usize],
+        headers: &Option<Vec<String>>,
+        build_data: B,
+        merge_data: M,
+    ) -> io::Result<D>
+    where
+        D: Send,
+        B: Fn(&mut dyn Iterator<Item = (usize, Vec<String>)>) -> io::Result<D> + Sync,
+        M: Fn(&mut D, D),
+    {
+        let results: Vec<io::Result<D>> = thread::scope(|scope| {
+            let handles: Vec<_> = chunks
+                .iter()
+                .zip(first_lines)
+                .map(|(&(start, end), &first_line)| {
+                    let build_data = &build_data;
+                    scope.spawn(move || {
+                        let mut file = File::open(path)?;
+                        file.seek(SeekFrom::Start(start))?;
+                        let buf_reader = BufReader::new(file.take(end - start));
+                        itertools::process_results(self.records(buf_reader), |records| {
+                            let records =
+                                records.map(|(line, record)| (first_line + line - 1, record));
+                            build_data(
+                                &mut headers
+                                    .clone()
+                                    .map(|headers| (1, headers))
+                                    .into_iter()
+                                    .chain(records),
+                            )
+                        })?
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+
+        let mut results = results.into_iter();
+        let mut data = results.next().expect("at least one chunk")?;
+        for other in results {
+            merge_data(&mut data, other?);
+        }
+        Ok(data)
+    }
+}
+
+/// Error of an option referencing a column that does not exist
+pub(crate) fn unknown_column(option: &str, column: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("unknown {} column {}", option, column),
+    )
+}
+
+/// Unique path of a temporary file for a copy of a stream
+fn spool_path() -> PathBuf {
+    static SPOOLS: AtomicUsize = AtomicUsize::new(0);
+    std::env::temp_dir().join(format!(
+        "analyze_data-{}-{}.tmp",
+        std::process::id(),
+        SPOOLS.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+/// Position in the group key of a group field referenced by 1-based index
+fn group_position(column: &str, option: &str) -> io::Result<usize> {
+    column
+        .parse::<usize>()
+        .ok()
+        .and_then(|index| index.checked_sub(1))
+        .ok_or_else(|| unknown_column(option, column))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reject::RejectReason;
+
+    /// Temporary file of the test, unique to the process
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("analyze_data_{}_{}", std::process::id(), name))
+    }
+
+    fn records(rows: &[&[&str]]) -> Vec<Vec<String>> {
+        rows.iter()
+            .map(|row| row.iter().map(|value| value.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_profile_reader_group_number() {
+        let profile = Profiler::new(Mode::GroupNumber)
+            .profile_reader("\"a,1\",1\n\"a,1\",3\nb,x\n".as_bytes())
+            .unwrap();
+        assert_eq!(profile.rows.len(), 2);
+        assert_eq!(profile.rows[0].group, vec!["b"]);
+        assert_eq!(profile.rows[0].number.as_ref().unwrap().error, 1);
+        assert_eq!(profile.rows[1].group, vec!["a,1"]);
+        assert_eq!(profile.rows[1].number.as_ref().unwrap().mean, 2.0);
+    }
+
+    #[test]
+    fn test_profile_records_csv() {
+        let profile = Profiler::new(Mode::Csv)
+            .top(1)
+            .profile_records(records(&[&["id", "name"], &["1", "x"], &["2", "x"]]))
+            .unwrap();
+        assert_eq!(profile.rows.len(), 2);
+        assert_eq!(profile.rows[0].column.as_deref(), Some("id"));
+        assert_eq!(profile.rows[0].number.as_ref().unwrap().sum, 3.0);
+        let id_type = profile.rows[0].inferred_type.as_ref().unwrap();
+        assert_eq!(id_type.value_type, crate::type_stats::ValueType::Integer);
+        assert_eq!(id_type.share, 1.0);
+        let name = profile.rows[1].string.as_ref().unwrap();
+        assert_eq!(name.cardinality, 1);
+        assert_eq!(name.top[0].value, "x");
+        assert_eq!(name.top[0].count, 2);
+    }
+
+    #[test]
+    fn test_profile_records_csv_group_by() {
+        let profile = Profiler::new(Mode::Csv)
+            .group_by(vec!["2".to_string(), "city".to_string()])
+            .profile_records(records(&[
+                &["id", "country", "city"],
+                &["1", "fr", "paris"],
+                &["2", "fr", "lyon"],
+                &["3", "fr", "paris"],
+                &["4", "de"],
+            ]))
+            .unwrap();
+        assert_eq!(profile.rows.len(), 3);
+        assert_eq!(profile.rows[0].group, vec!["fr", "paris"]);
+        assert_eq!(profile.rows[0].column.as_deref(), Some("id"));
+        assert_eq!(profile.rows[0].number.as_ref().unwrap().sum, 4.0);
+        assert_eq!(profile.rows[1].group, vec!["fr", "lyon"]);
+        assert_eq!(profile.rows[2].group, vec!["de", ""]);
+
+        let error = Profiler::new(Mode::Csv)
+            .group_by(vec!["nope".to_string()])
+            .profile_records(records(&[&["id"], &["1"]]))
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(error.to_string(), "unknown group by column nope");
+    }
+
+    #[test]
+    fn test_profile_records_csv_columns() {
+        let profile = Profiler::new(Mode::Csv)
+            .columns(vec!["price_*".to_string(), "1".to_string()])
+            .exclude_columns(vec!["price_gross".to_string()])
+            .profile_records(records(&[
+                &["id", "name", "price_net", "price_gross"],
+                &["1", "x", "2", "3"],
+            ]))
+            .unwrap();
+        let columns: Vec<_> = profile
+            .rows
+            .iter()
+            .map(|row| row.column.as_deref().unwrap())
+            .collect();
+        assert_eq!(columns, vec!["id", "price_net"]);
+
+        let error = Profiler::new(Mode::Csv)
+            .exclude_columns(vec!["nope".to_string()])
+            .profile_records(records(&[&["id"], &["1"]]))
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(error.to_string(), "unknown excluded column nope");
+    }
+
+    #[test]
+    fn test_profile_records_csv_no_header() {
+        let columns = |profile: Profile| -> Vec<String> {
+            profile
+                .rows
+                .into_iter()
+                .map(|row| row.column.unwrap())
+                .collect()
+        };
+        let profile = Profiler::new(Mode::Csv)
+            .no_header(true)
+            .profile_records(records(&[&["1", "x"], &["2", "y"]]))
+            .unwrap();
+        assert_eq!(profile.rows[0].number.as_ref().unwrap().count, 2);
+        assert_eq!(columns(profile), vec!["col1", "col2"]);
+
+        let profile = Profiler::new(Mode::Csv)
+            .header_names(vec!["id".to_string()])
+            .profile_records(records(&[&["1", "x"]]))
+            .unwrap();
+        assert_eq!(columns(profile), vec!["id", "col2"]);
+
+        let profile = Profiler::new(Mode::Csv)
+            .profile_records(records(&[]))
+            .unwrap();
+        assert!(profile.rows.is_empty());
+    }
+
+    #[test]
+    fn test_profile_records_csv_ragged() {
+        let rows = records(&[&["a", "b"], &["1", "2"], &["1"], &["1", "2", "3"], &["1"]]);
+        let profile = Profiler::new(Mode::Csv)
+            .profile_records(rows.clone())
+            .unwrap();
+        let ragged_rows = profile.ragged_rows.unwrap();
+        assert_eq!(ragged_rows.short_rows, 2);
+        assert_eq!(ragged_rows.long_rows, 1);
+        assert_eq!(ragged_rows.first_lines, vec![3, 4, 5]);
+        assert_eq!(profile.rows[1].string.as_ref().unwrap().empty, 0);
+
+        let profile = Profiler::new(Mode::Csv)
+            .missing_as_empty(true)
+            .profile_records(rows)
+            .unwrap();
+        assert_eq!(profile.rows[1].string.as_ref().unwrap().empty, 2);
+        assert_eq!(profile.rows[1].number.as_ref().unwrap().empty, 2);
+    }
+
+    #[test]
+    fn test_rejects() {
+        let profile = Profiler::new(Mode::GroupNumber)
+            .rejects(true)
+            .profile_reader("a,1\nb\na,x\n".as_bytes())
+            .unwrap();
+        let reasons: Vec<_> = profile
+            .rejects
+            .iter()
+            .map(|reject| (reject.line, reject.reason, reject.record.join(",")))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (2, RejectReason::MissingDelimiter, "b".to_string()),
+                (3, RejectReason::ParseFailure, "a,x".to_string()),
+            ]
+        );
+
+        let profile = Profiler::new(Mode::Csv)
+            .rejects(true)
+            .profile_records(records(&[
+                &["id", "name"],
+                &["1", "a"],
+                &["x", "b"],
+                &["3"],
+                &["4", "d"],
+            ]))
+            .unwrap();
+        let reasons: Vec<_> = profile
+            .rejects
+            .iter()
+            .map(|reject| (reject.line, reject.column.as_deref(), reject.reason))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (3, Some("id"), RejectReason::ParseFailure),
+                (4, None, RejectReason::FieldCountMismatch),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rejects_leading_errors() {
+        let profiler = Profiler::new(Mode::Csv).rejects(true);
+        let input = records(&[
+            &["id"],
+            &["x"],
+            &["y"],
+            &["z"],
+            &["1"],
+            &["2"],
+            &["3"],
+            &["4"],
+        ]);
+        let lines = |profile: Profile| -> (Vec<usize>, usize) {
+            let lines = profile.rejects.iter().map(|reject| reject.line).collect();
+            (lines, profile.dropped_rejects)
+        };
+
+        let profile = profiler.clone().profile_records(input.clone()).unwrap();
+        assert_eq!(lines(profile), (vec![2, 3, 4], 0));
+
+        let profile = profiler.max_rejects(2).profile_records(input).unwrap();
+        assert_eq!(lines(profile), (vec![2, 3], 1));
+    }
+
+    #[test]
+    fn test_profile_path_threads() {
+        let path = temp_path("profile_path_threads.csv");
+        let mut content = String::from("group,name,id,price\n");
+        for index in 0..500 {
+            content.push_str(&format!(
+                "g{},\"n,{}\",{},{:.2}\n",
+                index % 4,
+                index % 2,
+                index % 12,
+                1234.56 + (index % 24) as f64 * 0.37
+            ));
+        }
+        content.push_str("invalid\n");
+        std::fs::write(&path, content).unwrap();
+
+        for mode in [
+            Mode::Number,
+            Mode::String,
+            Mode::Date,
+            Mode::Count,
+            Mode::GroupNumber,
+            Mode::GroupString,
+            Mode::GroupCount,
+            Mode::Csv,
+        ] {
+            let profiler = Profiler::new(mode)
+                .top(3)
+                .percentiles(vec![90.0])
+                .rejects(true)
+                .correlations(true);
+            let profiler = match mode {
+                Mode::Csv => profiler.group_by(vec!["group".to_string()]),
+                _ => profiler,
+            };
+            let sequential = profiler.profile_path(&path).unwrap();
+            let parallel = profiler.threads(4).profile_path(&path).unwrap();
+            assert_eq!(sequential.rows.len(), parallel.rows.len());
+            assert_eq!(sequential.ragged_rows, parallel.ragged_rows);
+            assert_eq!(sequential.rejects, parallel.rejects);
+            assert_eq!(sequential.correlations.len(), parallel.correlations.len());
+            for (sequential, parallel) in sequential.correlations.iter().zip(&parallel.correlations)
+            {
+                assert_eq!(sequential.columns, parallel.columns);
+                assert_eq!(sequential.count, parallel.count);
+                for (sequential, parallel) in [
+                    (sequential.pearson, parallel.pearson),
+                    (sequential.spearman, parallel.spearman),
+                ] {
+                    assert!((sequential.unwrap() - parallel.unwrap()).abs() < 1e-9);
+                }
+            }
+            for (sequential_row, parallel_row) in sequential.rows.iter().zip(&parallel.rows) {
+                assert_eq!(sequential_row.group, parallel_row.group);
+                assert_eq!(sequential_row.column, parallel_row.column);
+                assert_eq!(sequential_row.string, parallel_row.string);
+                assert_eq!(sequential_row.date, parallel_row.date);
+                assert_eq!(sequential_row.counts, parallel_row.counts);
+                for (sequential_number, parallel_number) in [
+                    (&sequential_row.number, &parallel_row.number),
+                    (&sequential_row.length, &parallel_row.length),
+                ] {
+                    let (Some(sequential_number), Some(parallel_number)) =
+                        (sequential_number, parallel_number)
+                    else {
+                        assert_eq!(sequential_number, parallel_number);
+                        continue;
+                    };
+                    assert_eq!(sequential_number.count, parallel_number.count);
+                    assert_eq!(sequential_number.empty, parallel_number.empty);
+                    assert_eq!(sequential_number.error, parallel_number.error);
+                    assert_eq!(sequential_number.min, parallel_number.min);
+                    assert_eq!(sequential_number.max, parallel_number.max);
+                    assert_eq!(sequential_number.sum, parallel_number.sum);
+                    assert!((sequential_number.mean - parallel_number.mean).abs() < 1e-9);
+                    assert!((sequential_number.stddev - parallel_number.stddev).abs() < 1e-9);
+                    assert_eq!(sequential_number.median, parallel_number.median);
+                }
+            }
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_profile_path_threads_quoted_line_breaks() {
+        let path = temp_path("profile_path_threads_quoted_line_breaks.csv");
+        let mut content = String::from("\"group\nname\",note,n\n");
+        for index in 0..300 {
+            content.push_str(&format!(
+                "g{},\"line\n{}\n\"\"x\"\",\n\",{}\n",
+                index % 3,
+                index,
+                index % 7
+            ));
+        }
+        std::fs::write(&path, content).unwrap();
+
+        for mode in [Mode::GroupString, Mode::Csv] {
+            let profiler = Profiler::new(mode).rejects(true);
+            let sequential = profiler.profile_path(&path).unwrap();
+            for threads in [2, 4, 7] {
+                let parallel = profiler
+                    .clone()
+                    .threads(threads)
+                    .profile_path(&path)
+                    .unwrap();
+                assert_eq!(sequential.rows.len(), parallel.rows.len());
+                assert_eq!(sequential.ragged_rows, parallel.ragged_rows);
+                assert_eq!(sequential.rejects, parallel.rejects);
+                for (sequential_row, parallel_row) in sequential.rows.iter().zip(&parallel.rows) {
+                    assert_eq!(sequential_row.group, parallel_row.group);
+                    assert_eq!(sequential_row.column, parallel_row.column);
+                    assert_eq!(sequential_row.string, parallel_row.string);
+                    assert_eq!(
+                        sequential_row.number.as_ref().map(|number| number.sum),
+                        parallel_row.number.as_ref().map(|number| number.sum)
+                    );
+                }
+            }
+        }
+        let ragged_rows = Profiler::new(Mode::Csv)
+            .threads(4)
+            .profile_path(&path)
+            .unwrap()
+            .ragged_rows
+            .unwrap();
+        assert_eq!(ragged_rows.short_rows + ragged_rows.long_rows, 0);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_null_values() {
+        let profiler = Profiler::new(Mode::Number)
+            .null_values(vec!["NA".to_string(), "\\N".to_string()])
+            .null_values_ignore_case(true);
+        let profile = profiler
+            .profile_reader("1\nna\n\\N\nNULL\n\n".as_bytes())
+            .unwrap();
+        let number = profile.rows[0].number.as_ref().unwrap();
+        assert_eq!(number.count, 1);
+        assert_eq!(number.empty, 3);
+        assert_eq!(number.error, 1);
+
+        let profile = profiler
+            .null_values_ignore_case(false)
+            .profile_reader("NA\nna\n".as_bytes())
+            .unwrap();
+        assert_eq!(profile.rows[0].number.as_ref().unwrap().empty, 1);
+    }
+
+    #[test]
+    fn test_number_format() {
+        let profile = Profiler::new(Mode::Csv)
+            .number_format(NumberFormat::from_locale("de").unwrap())
+            .profile_records(records(&[&["price"], &["1.234,5"], &["2"]]))
+            .unwrap();
+        let price = &profile.rows[0];
+        assert_eq!(price.number.as_ref().unwrap().sum, 1236.5);
+        assert_eq!(
+            price.inferred_type.as_ref().unwrap().value_type,
+            crate::type_stats::ValueType::Float
+        );
+    }
+
+    #[test]
+    fn test_dates() {
+        let profile = Profiler::new(Mode::Date)
+            .date_formats(vec!["%d.%m.%Y".to_string()])
+            .profile_reader("2024-03-01\n02.03.2024\n1709251200\n\nx\n".as_bytes())
+            .unwrap();
+        let date = profile.rows[0].date.as_ref().unwrap();
+        assert_eq!((date.count, date.empty, date.error), (3, 1, 1));
+        assert_eq!(date.min.as_deref(), Some("2024-03-01T00:00:00"));
+        assert_eq!(date.max.as_deref(), Some("2024-03-02T00:00:00"));
+        assert_eq!(date.span, Some(86400.0));
+        assert_eq!(date.formats.len(), 3);
+
+        let profile = Profiler::new(Mode::Csv)
+            .profile_records(records(&[
+                &["id", "at"],
+                &["1709251200", "2024-03-01T10:00:00Z"],
+            ]))
+            .unwrap();
+        assert_eq!(profile.rows[0].date, None);
+        let at = profile.rows[1].date.as_ref().unwrap();
+        assert_eq!(at.formats[0].format, "rfc3339");
+    }
+
+    #[test]
+    fn test_time_buckets() {
+        let profile = Profiler::new(Mode::GroupNumber)
+            .time_buckets(vec!["1=day".parse().unwrap()])
+            .profile_reader(
+                "2024-03-02T10:00:00Z,1\n2024-03-01T23:00:00Z,2\n2024-03-02T08:00:00Z,3\n"
+                    .as_bytes(),
+            )
+            .unwrap();
+        let groups: Vec<&[String]> = profile.rows.iter().map(|row| &row.group[..]).collect();
+        assert_eq!(groups, [["2024-03-01"], ["2024-03-02"]]);
+        assert_eq!(profile.rows[1].number.as_ref().unwrap().sum, 4.0);
+
+        let profile = Profiler::new(Mode::Csv)
+            .group_by(vec!["city".to_string()])
+            .time_buckets(vec!["at=month".parse().unwrap()])
+            .profile_records(records(&[
+                &["at", "city", "n"],
+                &["2024-04-01", "a", "1"],
+                &["2024-03-31", "a", "2"],
+                &["2024-03-01", "b", "3"],
+            ]))
+            .unwrap();
+        let groups: Vec<&[String]> = profile.rows.iter().map(|row| &row.group[..]).collect();
+        assert_eq!(
+            groups,
+            [["b", "2024-03"], ["a", "2024-03"], ["a", "2024-04"]]
+        );
+        assert_eq!(profile.rows[0].column.as_deref(), Some("n"));
+
+        for (mode, time_bucket, message) in [
+            (
+                Mode::GroupNumber,
+                "foo=day",
+                "unknown time bucket column foo",
+            ),
+            (Mode::GroupCount, "0=day", "unknown time bucket column 0"),
+            (
+                Mode::GroupString,
+                "3=day",
+                "time bucket column 3 is beyond the group fields (1 at most)",
+            ),
+            (Mode::Csv, "nope=day", "unknown time bucket column nope"),
+        ] {
+            let error = Profiler::new(mode)
+                .time_buckets(vec![time_bucket.parse().unwrap()])
+                .profile_reader("at,n\n2024-03-01,1\n".as_bytes())
+                .unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+            assert_eq!(error.to_string(), message);
+        }
+    }
+
+    #[test]
+    fn test_bins() {
+        let input = "5,1\n25,2\n-3,3\n12,4\nx,5\n";
+        let profile = Profiler::new(Mode::GroupNumber)
+            .bins(vec!["1=width:10".parse().unwrap()])
+            .profile_reader(input.as_bytes())
+            .unwrap();
+        let groups: Vec<&str> = profile.rows.iter().map(|row| &row.group[0][..]).collect();
+        assert_eq!(
+            groups,
+            ["[-10, 0)", "[0, 10)", "[10, 20)", "[20, 30)", "<INVALID>"]
+        );
+
+        // Edges at the min, the estimated median and the max
+        let profiler =
+            Profiler::new(Mode::GroupString).bins(vec!["1=quantiles:2".parse().unwrap()]);
+        let profile = profiler.profile_reader(input.as_bytes()).unwrap();
+        let groups: Vec<&str> = profile.rows.iter().map(|row| &row.group[0][..]).collect();
+        assert_eq!(groups, ["[-3, 8.5)", "[8.5, 25]", "<INVALID>"]);
+        assert_eq!(profile.rows[0].string.as_ref().unwrap().count, 2);
+        let records = input
+            .lines()
+            .map(|line| line.split(',').map(str::to_string).collect());
+        assert_eq!(
+            profiler.profile_records(records).unwrap().rows,
+            profile.rows
+        );
+
+        let error = Profiler::new(Mode::GroupCount)
+            .bins(vec!["5=width:10".parse().unwrap()])
+            .profile_reader("1,2,3\n".as_bytes())
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(
+            error.to_string(),
+            "bin column 5 is beyond the group fields (2 at most)"
+        );
+    }
+
+    #[test]
+    fn test_histogram() {
+        let profile = Profiler::new(Mode::Csv)
+            .histogram(Some("fixed:0,10,2".parse().unwrap()))
+            .profile_records(records(&[
+                &["n", "s"],
+                &["1", "a"],
+                &["7", "b"],
+                &["12", ""],
+            ]))
+            .unwrap();
+        let counts: Vec<usize> = profile.rows[0]
+            .number
+            .as_ref()
+            .unwrap()
+            .histogram
+            .iter()
+            .map(|bin| bin.count)
+            .collect();
+        assert_eq!(counts, [1, 1, 1]);
+        assert!(profile.rows[0]
+            .length
+            .as_ref()
+            .unwrap()
+            .histogram
+            .is_empty());
+        assert!(profile.rows[1]
+            .number
+            .as_ref()
+            .unwrap()
+            .histogram
+            .is_empty());
+    }
+
+    #[test]
+    fn test_moments() {
+        let input = "2\n4\n4\n4\n5\n5\n7\n9\n";
+        let population = Profiler::new(Mode::Number)
+            .profile_reader(input.as_bytes())
+            .unwrap();
+        let number = population.rows[0].number.as_ref().unwrap();
+        assert!((number.stddev - 2.0).abs() < 1e-12);
+        assert!((number.variance - 4.0).abs() < 1e-12);
+        assert!((number.cv.unwrap() - 0.4).abs() < 1e-12);
+        assert!((number.skewness.unwrap() - 0.65625).abs() < 1e-12);
+        assert!((number.kurtosis.unwrap() + 0.21875).abs() < 1e-12);
+
+        let sample = Profiler::new(Mode::Number)
+            .sample_stddev(true)
+            .confidence(99.0)
+            .profile_reader(input.as_bytes())
+            .unwrap();
+        let number = sample.rows[0].number.as_ref().unwrap();
+        assert!((number.variance - 32.0 / 7.0).abs() < 1e-12);
+        assert!((number.std_error.unwrap() - (4.0f64 / 7.0).sqrt()).abs() < 1e-12);
+        let interval = number.mean_interval.as_ref().unwrap();
+        assert_eq!(interval.confidence, 99.0);
+        // t(0.995, 7) = 3.4995
+        assert!((interval.upper - 5.0 - 3.4995 * (4.0f64 / 7.0).sqrt()).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_stats() {
+        let stats: Vec<Stat> = ["top_2", "number_p90", "count"]
+            .iter()
+            .map(|name| Stat::parse(name, Mode::Csv).unwrap())
+            .collect();
+        let profile = Profiler::new(Mode::Csv)
+            .percentiles(vec![50.0])
+            .stats(stats.clone())
+            .profile_records(records(&[&["n"], &["1"], &["2"], &["2"]]))
+            .unwrap();
+        assert_eq!(profile.stats, stats);
+        let row = &profile.rows[0];
+        let percentiles: Vec<f64> = row
+            .number
+            .as_ref()
+            .unwrap()
+            .percentiles
+            .iter()
+            .map(|percentile| percentile.percentile)
+            .collect();
+        assert_eq!(percentiles, [50.0, 90.0]);
+        assert_eq!(row.string.as_ref().unwrap().top.len(), 2);
+        let values: Vec<String> = stats
+            .iter()
+            .map(|stat| stat.value(row, None).format(1))
+            .collect();
+        assert_eq!(values, ["1 (1, 33.3%)", "2.0", "3"]);
+
+        let defaults = Profiler::new(Mode::Count).profile_reader("a\n".as_bytes());
+        assert_eq!(
+            defaults.unwrap().stats,
+            Stat::defaults(Mode::Count, &[], 0, false)
+        );
+    }
+
+    #[test]
+    fn test_count() {
+        let profile = Profiler::new(Mode::Count)
+            .min_count(2)
+            .profile_reader("b\na\nb\nc\na\nb\n".as_bytes())
+            .unwrap();
+        let counts = profile.rows[0].counts.as_ref().unwrap();
+        assert_eq!((counts.total, counts.distinct), (6, 3));
+        let values: Vec<(&str, usize)> = counts
+            .values
+            .iter()
+            .map(|value_count| (&value_count.value[..], value_count.count))
+            .collect();
+        assert_eq!(values, [("b", 3), ("a", 2)]);
+        assert_eq!(counts.values[0].share, 0.5);
+        assert!((counts.values[1].cumulative_share - 5.0 / 6.0).abs() < 1e-9);
+
+        let profile = Profiler::new(Mode::GroupCount)
+            .top(1)
+            .profile_reader("x,b\nx,a\nx,b\ny,c\n".as_bytes())
+            .unwrap();
+        assert_eq!(profile.rows.len(), 2);
+        assert_eq!(profile.rows[0].group, ["y"]);
+        let counts = profile.rows[1].counts.as_ref().unwrap();
+        assert_eq!((counts.total, counts.values.len()), (3, 1));
+        assert_eq!(counts.values[0].value, "b");
+    }
+
+    #[test]
+    fn test_correlations() {
+        let profile = Profiler::new(Mode::Csv)
+            .correlations(true)
+            .profile_records(records(&[
+                &["x", "name", "y", "z"],
+                &["1", "a", "2", "9"],
+                &["2", "b", "4", "4"],
+                &["3", "c", "6", "1"],
+                &["4", "d", "", "0"],
+            ]))
+            .unwrap();
+        let correlations: Vec<([&str; 2], usize)> = profile
+            .correlations
+            .iter()
+            .map(|correlation| {
+                (
+                    [&correlation.columns[0][..], &correlation.columns[1][..]],
+                    correlation.count,
+                )
+            })
+            .collect();
+        assert_eq!(
+            correlations,
+            [(["x", "y"], 3), (["x", "z"], 4), (["y", "z"], 3)]
+        );
+        assert!((profile.correlations[0].pearson.unwrap() - 1.0).abs() < 1e-9);
+        assert!(profile.correlations[1].pearson.unwrap() < -0.9);
+        assert!((profile.correlations[1].spearman.unwrap() + 1.0).abs() < 1e-9);
+        assert!(Profiler::new(Mode::Csv)
+            .profile_records(records(&[&["x", "y"], &["1", "2"]]))
+            .unwrap()
+            .correlations
+            .is_empty());
+    }
+
+    #[test]
+    fn test_profile_records_string() {
+        let profile = Profiler::new(Mode::String)
+            .profile_records(records(&[&["ab"], &[""]]))
+            .unwrap();
+        let row = &profile.rows[0];
+        assert_eq!(row.string.as_ref().unwrap().empty, 1);
+        assert_eq!(row.length.as_ref().unwrap().max, Some(2.0));
+    }
+}
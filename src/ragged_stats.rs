@@ -0,0 +1,70 @@
+/// Number of offending line numbers to keep
+const FIRST_LINES: usize = 10;
+
+/// Counts of the rows with fewer or more fields than the headers
+#[derive(Clone, Debug, Default)]
+pub struct RaggedStats {
+    short_count: usize,
+    long_count: usize,
+    first_lines: Vec<usize>,
+}
+
+impl RaggedStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Account for a row with `length` fields, starting at `line`
+    pub fn add(&mut self, line: usize, length: usize, expected_length: usize) {
+        if length < expected_length {
+            self.short_count += 1;
+        } else if length > expected_length {
+            self.long_count += 1;
+        } else {
+            return;
+        }
+        if self.first_lines.len() < FIRST_LINES {
+            self.first_lines.push(line);
+        }
+    }
+    /// Combine with stats of another part of the input, as if all rows were added to this one
+    pub fn merge(&mut self, other: &Self) {
+        self.short_count += other.short_count;
+        self.long_count += other.long_count;
+        self.first_lines.extend(&other.first_lines);
+        self.first_lines.sort_unstable();
+        self.first_lines.truncate(FIRST_LINES);
+    }
+    pub fn short_count(&self) -> usize {
+        self.short_count
+    }
+    pub fn long_count(&self) -> usize {
+        self.long_count
+    }
+    /// Line numbers of the first short or long rows
+    pub fn first_lines(&self) -> &[usize] {
+        &self.first_lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_merge() {
+        let mut stats = RaggedStats::new();
+        stats.add(2, 3, 3);
+        stats.add(3, 2, 3);
+        let mut other = RaggedStats::new();
+        for line in 10..30 {
+            other.add(line, 4, 3);
+        }
+        stats.merge(&other);
+        assert_eq!(stats.short_count(), 1);
+        assert_eq!(stats.long_count(), 20);
+        assert_eq!(
+            stats.first_lines(),
+            &[3, 10, 11, 12, 13, 14, 15, 16, 17, 18]
+        );
+    }
+}
@@ -0,0 +1,252 @@
+use std::io::{self, BufRead, Lines};
+
+/// Describes how fields are delimited, quoted and escaped (RFC 4180 by default)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Dialect {
+    pub delimiter: char,
+    pub quote: char,
+    pub escape: Option<char>,
+}
+
+impl Default for Dialect {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            quote: '"',
+            escape: None,
+        }
+    }
+}
+
+/// Quote a field for delimited output if it contains the delimiter, a quote or a line break
+pub fn quote_field(field: &str, delimiter: char) -> String {
+    if field.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Quote aware reader yielding one record (list of fields) per iteration.
+/// A quote is only significant at the start of a field. Inside a quoted field the delimiter and
+/// line breaks are literal and a doubled quote is an escaped quote. An optional escape character
+/// makes the next character literal, both inside and outside quotes.
+pub struct RecordReader<R: BufRead> {
+    lines: Lines<R>,
+    dialect: Dialect,
+    /// Number of lines read so far
+    line: usize,
+    /// Line number of the first line of the last record
+    record_line: usize,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum State {
+    FieldStart,
+    Unquoted,
+    Quoted,
+    QuoteInQuoted,
+    AfterQuoted,
+}
+
+impl<R: BufRead> RecordReader<R> {
+    pub fn new(buf_reader: R, dialect: Dialect) -> Self {
+        Self {
+            lines: buf_reader.lines(),
+            dialect,
+            line: 0,
+            record_line: 0,
+        }
+    }
+
+    /// Yield each record with the (1-based) line number of its first line
+    pub fn with_line_numbers(mut self) -> impl Iterator<Item = io::Result<(usize, Vec<String>)>> {
+        std::iter::from_fn(move || {
+            let record = self.next()?;
+            Some(record.map(|record| (self.record_line, record)))
+        })
+    }
+
+    fn parse_record(&mut self, first_line: String) -> io::Result<Vec<String>> {
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut state = State::FieldStart;
+        let mut line = first_line;
+
+        loop {
+            state = parse_line(&line, state, self.dialect, &mut fields, &mut field);
+            if state != State::Quoted {
+                break;
+            }
+            match self.lines.next() {
+                Some(next_line) => {
+                    self.line += 1;
+                    field.push('\n');
+                    line = next_line?;
+                }
+                None => break,
+            }
+        }
+        fields.push(field);
+        Ok(fields)
+    }
+}
+
+/// Parse a line (without its line break) from the given state into the fields, the last one
+/// still in `field`. Returns the state at the end of the line.
+fn parse_line(
+    line: &str,
+    mut state: State,
+    dialect: Dialect,
+    fields: &mut Vec<String>,
+    field: &mut String,
+) -> State {
+    let Dialect {
+        delimiter,
+        quote,
+        escape,
+    } = dialect;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if state == State::QuoteInQuoted {
+            if c == quote {
+                field.push(c);
+                state = State::Quoted;
+                continue;
+            }
+            state = State::AfterQuoted;
+        }
+        if Some(c) == escape && c != quote && state != State::AfterQuoted {
+            if let Some(next) = chars.next() {
+                field.push(next);
+            }
+            if state == State::FieldStart {
+                state = State::Unquoted;
+            }
+            continue;
+        }
+        match state {
+            State::FieldStart if c == quote => state = State::Quoted,
+            State::Quoted if c == quote => state = State::QuoteInQuoted,
+            State::Quoted => field.push(c),
+            _ if c == delimiter => {
+                fields.push(std::mem::take(field));
+                state = State::FieldStart;
+            }
+            _ => {
+                field.push(c);
+                if state == State::FieldStart {
+                    state = State::Unquoted;
+                }
+            }
+        }
+    }
+    state
+}
+
+/// Whether the record continues on the next line, the line (without its line break) ending inside
+/// a quoted field. `quoted` is whether the line starts inside a quoted field of the previous line.
+pub(crate) fn ends_quoted(line: &str, quoted: bool, dialect: Dialect) -> bool {
+    let state = if quoted {
+        State::Quoted
+    } else {
+        State::FieldStart
+    };
+    parse_line(line, state, dialect, &mut Vec::new(), &mut String::new()) == State::Quoted
+}
+
+impl<R: BufRead> Iterator for RecordReader<R> {
+    type Item = io::Result<Vec<String>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = self.lines.next()?;
+        self.line += 1;
+        self.record_line = self.line;
+        Some(line.and_then(|line| self.parse_record(line)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(input: &str, dialect: Dialect) -> Vec<Vec<String>> {
+        RecordReader::new(input.as_bytes(), dialect)
+            .collect::<io::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_plain() {
+        let records = read("a,b,c\n1,,3\n", Dialect::default());
+        assert_eq!(records, vec![vec!["a", "b", "c"], vec!["1", "", "3"]]);
+    }
+
+    #[test]
+    fn test_quoted_delimiter() {
+        let records = read("\"a,b\",c\n", Dialect::default());
+        assert_eq!(records, vec![vec!["a,b", "c"]]);
+    }
+
+    #[test]
+    fn test_doubled_quote() {
+        let records = read("\"say \"\"hi\"\"\",x\n", Dialect::default());
+        assert_eq!(records, vec![vec!["say \"hi\"", "x"]]);
+    }
+
+    #[test]
+    fn test_embedded_newline() {
+        let records = read("\"multi\nline\",x\ny,z\n", Dialect::default());
+        assert_eq!(records, vec![vec!["multi\nline", "x"], vec!["y", "z"]]);
+    }
+
+    #[test]
+    fn test_ends_quoted() {
+        let dialect = Dialect::default();
+        assert!(ends_quoted("a,\"multi", false, dialect));
+        assert!(!ends_quoted("line\",b", true, dialect));
+        assert!(ends_quoted("say \"\"hi", true, dialect));
+        assert!(!ends_quoted("5\" pipe,x", false, dialect));
+    }
+
+    #[test]
+    fn test_line_numbers() {
+        let lines: Vec<usize> =
+            RecordReader::new("a\n\"multi\nline\"\nb\n".as_bytes(), Dialect::default())
+                .with_line_numbers()
+                .map(|record| record.unwrap().0)
+                .collect();
+        assert_eq!(lines, vec![1, 2, 4]);
+    }
+
+    #[test]
+    fn test_unterminated_quote() {
+        let records = read("\"open,x\n", Dialect::default());
+        assert_eq!(records, vec![vec!["open,x"]]);
+    }
+
+    #[test]
+    fn test_quote_mid_field_is_literal() {
+        let records = read("5\" pipe,x\n", Dialect::default());
+        assert_eq!(records, vec![vec!["5\" pipe", "x"]]);
+    }
+
+    #[test]
+    fn test_escape_char() {
+        let dialect = Dialect {
+            delimiter: ';',
+            quote: '\'',
+            escape: Some('\\'),
+        };
+        let records = read("a\\;b;'it\\'s';c\n", dialect);
+        assert_eq!(records, vec![vec!["a;b", "it's", "c"]]);
+    }
+
+    #[test]
+    fn test_quote_field() {
+        assert_eq!(quote_field("plain", ','), "plain");
+        assert_eq!(quote_field("a,b", ','), "\"a,b\"");
+        assert_eq!(quote_field("a,b", ';'), "a,b");
+        assert_eq!(quote_field("say \"hi\"", ','), "\"say \"\"hi\"\"\"");
+    }
+}
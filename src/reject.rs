@@ -0,0 +1,184 @@
+use crate::record_reader::quote_field;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Write};
+
+/// Why a record (or one of its values) could not be profiled
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RejectReason {
+    /// The value is not a number
+    ParseFailure,
+    /// The record has no group (group modes)
+    MissingDelimiter,
+    /// The record has fewer or more fields than the headers (csv mode)
+    FieldCountMismatch,
+}
+
+impl fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self {
+            Self::ParseFailure => "parse failure",
+            Self::MissingDelimiter => "missing delimiter",
+            Self::FieldCountMismatch => "field-count mismatch",
+        };
+        f.write_str(reason)
+    }
+}
+
+/// Default number of rejects kept per reason and column
+pub const MAX_REJECTS: usize = 1000;
+
+/// Erroring record with the line number of its first line
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reject {
+    pub line: usize,
+    /// Column name (csv mode)
+    pub column: Option<String>,
+    pub reason: RejectReason,
+    pub record: Vec<String>,
+}
+
+/// Erroring records in line order, keeping the first ones of each reason and column so that the
+/// memory stays bounded on dirty input
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Rejects {
+    limit: usize,
+    rejects: Vec<Reject>,
+    /// Number of rejects of each reason and column, kept or not
+    counts: HashMap<(RejectReason, Option<String>), usize>,
+}
+
+impl Rejects {
+    /// Keep at most `limit` rejects of each reason and column
+    pub(crate) fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            ..Self::default()
+        }
+    }
+    pub(crate) fn add(&mut self, reject: Reject) {
+        let count = self
+            .counts
+            .entry((reject.reason, reject.column.clone()))
+            .or_default();
+        *count += 1;
+        if *count <= self.limit {
+            self.rejects.push(reject);
+        }
+    }
+    /// Combine with the rejects of the following part of the input, as if they were added to this
+    /// one
+    pub(crate) fn merge(&mut self, other: Self) {
+        for reject in other.rejects {
+            self.add(reject);
+        }
+        // Rejects not kept by the other part
+        for (key, other_count) in other.counts {
+            *self.counts.entry(key).or_default() += other_count - other_count.min(other.limit);
+        }
+    }
+    /// Keep the rejects of the given reasons and columns only
+    pub(crate) fn retain(&mut self, f: impl Fn(RejectReason, Option<&String>) -> bool) {
+        self.rejects
+            .retain(|reject| f(reject.reason, reject.column.as_ref()));
+        self.counts
+            .retain(|(reason, column), _count| f(*reason, column.as_ref()));
+    }
+    /// Kept rejects, and the number of rejects over the limit
+    pub(crate) fn into_parts(self) -> (Vec<Reject>, usize) {
+        let dropped = self
+            .counts
+            .values()
+            .map(|count| count - count.min(&self.limit))
+            .sum();
+        (self.rejects, dropped)
+    }
+}
+
+/// Write the rejects as csv with a `line,column,reason,record` header, the record fields joined
+/// with the input delimiter
+pub fn write_rejects<W: Write>(
+    mut writer: W,
+    rejects: &[Reject],
+    delimiter: char,
+) -> io::Result<()> {
+    writeln!(writer, "line,column,reason,record")?;
+    for reject in rejects {
+        let record = reject
+            .record
+            .iter()
+            .map(|field| quote_field(field, delimiter))
+            .collect::<Vec<String>>()
+            .join(&delimiter.to_string());
+        writeln!(
+            writer,
+            "{},{},{},{}",
+            reject.line,
+            quote_field(reject.column.as_deref().unwrap_or_default(), ','),
+            reject.reason,
+            quote_field(&record, ',')
+        )?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reject(line: usize, column: &str, reason: RejectReason) -> Reject {
+        Reject {
+            line,
+            column: Some(column.to_string()),
+            reason,
+            record: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_rejects_limit() {
+        let lines = |rejects: Rejects| -> (Vec<usize>, usize) {
+            let (rejects, dropped) = rejects.into_parts();
+            (rejects.iter().map(|reject| reject.line).collect(), dropped)
+        };
+        let mut all = Rejects::new(2);
+        let mut first = Rejects::new(2);
+        let mut second = Rejects::new(2);
+        for line in 1..=6 {
+            let column = if line % 3 == 0 { "b" } else { "a" };
+            let reject = reject(line, column, RejectReason::ParseFailure);
+            all.add(reject.clone());
+            if line <= 3 { &mut first } else { &mut second }.add(reject);
+        }
+        first.merge(second);
+        assert_eq!(lines(all.clone()), (vec![1, 2, 3, 6], 2));
+        assert_eq!(lines(first), lines(all.clone()));
+
+        all.retain(|_reason, column| column.is_some_and(|column| column == "b"));
+        assert_eq!(lines(all), (vec![3, 6], 0));
+    }
+
+    #[test]
+    fn test_write_rejects() {
+        let rejects = vec![
+            Reject {
+                line: 2,
+                column: Some("price".to_string()),
+                reason: RejectReason::ParseFailure,
+                record: vec!["a,b".to_string(), "x".to_string()],
+            },
+            Reject {
+                line: 5,
+                column: None,
+                reason: RejectReason::MissingDelimiter,
+                record: vec!["x".to_string()],
+            },
+        ];
+        let mut output = Vec::new();
+        write_rejects(&mut output, &rejects, ';').unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "line,column,reason,record\n2,price,parse failure,\"a,b;x\"\n5,,missing delimiter,x\n"
+        );
+    }
+}
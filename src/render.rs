@@ -0,0 +1,470 @@
+use crate::output_format::OutputFormat;
+use crate::profile::Correlation;
+use crate::record_reader::quote_field;
+use crate::report::{Report, ReportRow, Section, StatValue};
+use crate::stat::Stat;
+use cli_table::{
+    format::{HorizontalLine, Justify, Separator, VerticalLine},
+    print_stdout, Cell, CellStruct, Style, Table,
+};
+use itertools::Itertools;
+use serde_json::{json, Map, Value};
+
+/// Width of the longest histogram bar
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// Print a report to stdout, the stats then the sections. The json formats keep the full
+/// precision of the typed values, and ndjson only has the stats rows.
+pub fn print(report: &Report, output_format: OutputFormat, precision: usize) {
+    match output_format {
+        OutputFormat::Table => {
+            print_table(report, precision);
+            for section in &report.sections {
+                print_table_section(section, precision);
+            }
+        }
+        OutputFormat::Csv(delimiter) => {
+            print_csv(report, delimiter, precision);
+            for section in &report.sections {
+                print_csv_section(section, delimiter, precision);
+            }
+        }
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&json_document(report)).unwrap()
+        ),
+        OutputFormat::Ndjson => {
+            for row in &report.rows {
+                println!("{}", json_row(report, row));
+            }
+        }
+    }
+}
+
+fn justify(stat: &Stat) -> Justify {
+    if stat.left_aligned() {
+        Justify::Left
+    } else {
+        Justify::Right
+    }
+}
+
+fn print_table(report: &Report, precision: usize) {
+    let separator = Separator::builder()
+        .title(Some(HorizontalLine::default()))
+        .column(Some(VerticalLine::default()))
+        .build();
+
+    if report.transposed {
+        let table = report
+            .rows
+            .iter()
+            .flat_map(|row| report.stats.iter().zip(&row.values))
+            .map(|(stat, value)| {
+                vec![
+                    stat.title
+                        .as_str()
+                        .cell()
+                        .justify(Justify::Right)
+                        .bold(true),
+                    value.format(precision).cell().justify(justify(stat)),
+                ]
+            })
+            .collect::<Vec<Vec<CellStruct>>>()
+            .table()
+            .separator(separator);
+        print_stdout(table).unwrap();
+        return;
+    }
+
+    let mut title: Vec<CellStruct> = [""]
+        .iter()
+        .cycle()
+        .take(report.group_length())
+        .map(|v| v.cell())
+        .collect();
+    title.extend(
+        report
+            .stats
+            .iter()
+            .map(|stat| stat.title.as_str().cell().justify(justify(stat)).bold(true)),
+    );
+
+    let table = report
+        .rows
+        .iter()
+        .map(|row| {
+            let mut cells: Vec<CellStruct> = row.keys().map(|v| v.cell()).collect();
+            cells.extend(
+                report
+                    .stats
+                    .iter()
+                    .zip(&row.values)
+                    .map(|(stat, value)| value.format(precision).cell().justify(justify(stat))),
+            );
+            cells
+        })
+        .table()
+        .separator(separator)
+        .title(title)
+        .bold(true);
+
+    print_stdout(table).unwrap();
+}
+
+fn print_csv(report: &Report, delimiter: char, precision: usize) {
+    let quote = |value: &str| quote_field(value, delimiter);
+    let delimiter_str = delimiter.to_string();
+
+    if report.transposed {
+        for row in &report.rows {
+            for (stat, value) in report.stats.iter().zip(&row.values) {
+                println!(
+                    "{}{}{}",
+                    stat.name,
+                    delimiter,
+                    quote(&value.format(precision))
+                );
+            }
+        }
+        return;
+    }
+
+    println!(
+        "{}{}",
+        delimiter_str.repeat(report.group_length()),
+        report
+            .stats
+            .iter()
+            .map(|stat| &stat.name)
+            .join(&delimiter_str)
+    );
+    for row in &report.rows {
+        println!(
+            "{}",
+            row.keys()
+                .map(|value| quote(value))
+                .chain(
+                    row.values
+                        .iter()
+                        .map(|value| quote(&value.format(precision)))
+                )
+                .join(&delimiter_str)
+        );
+    }
+}
+
+/// Bound of a histogram bin at the output precision, empty when unbounded
+fn format_bound(bound: Option<f64>, precision: usize) -> String {
+    bound
+        .map(|bound| format!("{:.*}", precision, bound))
+        .unwrap_or_default()
+}
+
+/// Correlation coefficient with at least 2 decimals
+fn format_coefficient(coefficient: Option<f64>, precision: usize) -> String {
+    coefficient
+        .map(|coefficient| format!("{:.*}", precision.max(2), coefficient))
+        .unwrap_or_default()
+}
+
+/// Error examples as a Debug formatted list
+fn format_examples(examples: &[String]) -> String {
+    examples
+        .iter()
+        .map(|example| format!("{:?}", example))
+        .join(", ")
+}
+
+fn print_table_section(section: &Section, precision: usize) {
+    match section {
+        Section::ErrorExamples(rows) => {
+            println!("Error examples");
+            let table = rows
+                .iter()
+                .map(|(group, column, examples)| {
+                    group
+                        .iter()
+                        .chain(column)
+                        .cloned()
+                        .chain([format_examples(examples)])
+                        .map(|value| value.cell())
+                        .collect::<Vec<_>>()
+                })
+                .table();
+            print_stdout(table).unwrap();
+        }
+        Section::Histograms(histograms) => {
+            println!("Histogram");
+            let table = histograms
+                .iter()
+                .flat_map(|(group, column, bins)| {
+                    let max_count = bins.iter().map(|bin| bin.count).max().unwrap_or(0).max(1);
+                    bins.iter().enumerate().map(move |(index, bin)| {
+                        let range = format!(
+                            "{}{}, {}{}",
+                            if bin.lower.is_some() { '[' } else { '(' },
+                            bin.lower.map_or("-inf".to_string(), |lower| {
+                                format_bound(Some(lower), precision)
+                            }),
+                            bin.upper.map_or("inf".to_string(), |upper| {
+                                format_bound(Some(upper), precision)
+                            }),
+                            if index + 1 == bins.len() && bin.upper.is_some() {
+                                ']'
+                            } else {
+                                ')'
+                            }
+                        );
+                        // Non-empty bins get at least one character
+                        let bar = "#".repeat(
+                            (bin.count * HISTOGRAM_WIDTH / max_count).max(bin.count.min(1)),
+                        );
+                        group
+                            .iter()
+                            .chain(column)
+                            .map(|value| value.cell())
+                            .chain([
+                                range.cell().justify(Justify::Right),
+                                bin.count.cell().justify(Justify::Right),
+                                bar.cell(),
+                            ])
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .table();
+            print_stdout(table).unwrap();
+        }
+        Section::Correlations(correlations) => print_correlation_tables(correlations, precision),
+    }
+}
+
+/// Print the Pearson and Spearman correlation matrices
+fn print_correlation_tables(correlations: &[Correlation], precision: usize) {
+    let columns: Vec<&String> = correlations
+        .iter()
+        .flat_map(|correlation| &correlation.columns)
+        .unique()
+        .collect();
+    for (title, spearman) in [
+        ("Pearson correlations", false),
+        ("Spearman correlations", true),
+    ] {
+        println!("{}", title);
+        let table = columns
+            .iter()
+            .map(|&row_column| {
+                [row_column.cell().bold(true)]
+                    .into_iter()
+                    .chain(columns.iter().map(|&column| {
+                        let coefficient = if column == row_column {
+                            Some(1.0)
+                        } else {
+                            correlations
+                                .iter()
+                                .find(|correlation| {
+                                    correlation.columns.contains(column)
+                                        && correlation.columns.contains(row_column)
+                                })
+                                .and_then(|correlation| {
+                                    if spearman {
+                                        correlation.spearman
+                                    } else {
+                                        correlation.pearson
+                                    }
+                                })
+                        };
+                        format_coefficient(coefficient, precision)
+                            .cell()
+                            .justify(Justify::Right)
+                    }))
+                    .collect::<Vec<_>>()
+            })
+            .table()
+            .title(
+                [String::new()]
+                    .into_iter()
+                    .chain(columns.iter().map(|column| column.to_string()))
+                    .map(|column| column.cell().bold(true))
+                    .collect::<Vec<_>>(),
+            );
+        print_stdout(table).unwrap();
+    }
+}
+
+fn print_csv_section(section: &Section, delimiter: char, precision: usize) {
+    let quote = |value: &str| quote_field(value, delimiter);
+    let delimiter_str = delimiter.to_string();
+    println!();
+    match section {
+        Section::ErrorExamples(rows) => {
+            println!("error_examples");
+            for (group, column, examples) in rows {
+                println!(
+                    "{}",
+                    group
+                        .iter()
+                        .chain(column)
+                        .cloned()
+                        .chain([format_examples(examples)])
+                        .map(|value| quote(&value))
+                        .join(&delimiter_str)
+                );
+            }
+        }
+        Section::Histograms(histograms) => {
+            println!("histogram");
+            for (group, column, bins) in histograms {
+                for bin in bins {
+                    println!(
+                        "{}",
+                        group
+                            .iter()
+                            .chain(column)
+                            .cloned()
+                            .chain([
+                                format_bound(bin.lower, precision),
+                                format_bound(bin.upper, precision),
+                                bin.count.to_string()
+                            ])
+                            .map(|value| quote(&value))
+                            .join(&delimiter_str)
+                    );
+                }
+            }
+        }
+        Section::Correlations(correlations) => {
+            println!("correlations");
+            println!(
+                "{}",
+                ["column_a", "column_b", "count", "pearson", "spearman"].join(&delimiter_str)
+            );
+            for correlation in correlations {
+                println!(
+                    "{}",
+                    [
+                        quote(&correlation.columns[0]),
+                        quote(&correlation.columns[1]),
+                        correlation.count.to_string(),
+                        format_coefficient(correlation.pearson, precision),
+                        format_coefficient(correlation.spearman, precision),
+                    ]
+                    .join(&delimiter_str)
+                );
+            }
+        }
+    }
+}
+
+/// Typed JSON value of a statistic, null when missing (or not finite), percentages from 0 to 100
+fn json_value(value: &StatValue) -> Value {
+    match value {
+        StatValue::Missing => Value::Null,
+        StatValue::Count(count) => json!(count),
+        StatValue::Number(num) | StatValue::Scientific(num) => json!(num),
+        StatValue::Percent(share) => json!(share * 100.0),
+        StatValue::Text(text) => json!(text),
+    }
+}
+
+/// Object with the group keys (when grouped) and the column (csv mode)
+fn json_keys(group: &[String], column: &Option<String>) -> Map<String, Value> {
+    let mut object = Map::new();
+    if !group.is_empty() {
+        object.insert("group".to_string(), json!(group));
+    }
+    if let Some(column) = column {
+        object.insert("column".to_string(), json!(column));
+    }
+    object
+}
+
+/// Row object with the keys then the statistics by name
+fn json_row(report: &Report, row: &ReportRow) -> Value {
+    let mut object = json_keys(&row.group, &row.column);
+    for (stat, value) in report.stats.iter().zip(&row.values) {
+        object.insert(stat.name.clone(), json_value(value));
+    }
+    Value::Object(object)
+}
+
+/// Document with the rows, then the sections by name
+fn json_document(report: &Report) -> Value {
+    let mut document = Map::new();
+    document.insert(
+        "rows".to_string(),
+        report
+            .rows
+            .iter()
+            .map(|row| json_row(report, row))
+            .collect(),
+    );
+    for section in &report.sections {
+        let keyed = |group, column, name: &str, values: Value| {
+            let mut object = json_keys(group, column);
+            object.insert(name.to_string(), values);
+            Value::Object(object)
+        };
+        let (name, value) = match section {
+            Section::ErrorExamples(rows) => (
+                "error_examples",
+                rows.iter()
+                    .map(|(group, column, examples)| {
+                        keyed(group, column, "examples", json!(examples))
+                    })
+                    .collect(),
+            ),
+            Section::Histograms(histograms) => (
+                "histograms",
+                histograms
+                    .iter()
+                    .map(|(group, column, bins)| keyed(group, column, "bins", json!(bins)))
+                    .collect(),
+            ),
+            Section::Correlations(correlations) => ("correlations", json!(correlations)),
+        };
+        document.insert(name.to_string(), value);
+    }
+    Value::Object(document)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mode::Mode;
+    use crate::profiler::Profiler;
+    use crate::stat::Stat;
+
+    #[test]
+    fn test_json_document() {
+        let profile = Profiler::new(Mode::Csv)
+            .stats(vec![
+                Stat::parse("count", Mode::Csv).unwrap(),
+                Stat::parse("number_mean", Mode::Csv).unwrap(),
+            ])
+            .error_examples(1)
+            .profile_records(vec![
+                vec!["a".to_string()],
+                vec!["1".to_string()],
+                vec!["x".to_string()],
+                vec!["2".to_string()],
+            ])
+            .unwrap();
+        assert_eq!(
+            json_document(&Report::new(&profile)),
+            json!({
+                "rows": [{"column": "a", "count": 3, "number_mean": 1.5}],
+                "error_examples": [{"column": "a", "examples": ["x"]}],
+            })
+        );
+    }
+
+    #[test]
+    fn test_json_value() {
+        assert_eq!(json_value(&StatValue::Missing), Value::Null);
+        assert_eq!(json_value(&StatValue::Number(f64::NAN)), Value::Null);
+        assert_eq!(json_value(&StatValue::Count(3)), json!(3));
+        assert_eq!(json_value(&StatValue::Percent(0.25)), json!(25.0));
+        assert_eq!(json_value(&StatValue::Text("a".to_string())), json!("a"));
+    }
+}
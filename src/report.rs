@@ -0,0 +1,214 @@
+use crate::mode::Mode;
+use crate::profile::{Correlation, HistogramBin, NumberSummary, Profile};
+use crate::stat::Stat;
+
+/// Typed value of a statistic, formatted by the renderer
+#[derive(Clone, Debug, PartialEq)]
+pub enum StatValue {
+    /// No value for the row, like the date stats of a column without dates
+    Missing,
+    Count(usize),
+    /// Number at the output precision
+    Number(f64),
+    /// Number in scientific notation (sums)
+    Scientific(f64),
+    /// Share (0-1) as a percentage with one decimal
+    Percent(f64),
+    Text(String),
+}
+
+impl StatValue {
+    pub fn format(&self, precision: usize) -> String {
+        match self {
+            StatValue::Missing => String::new(),
+            StatValue::Count(count) => count.to_string(),
+            StatValue::Number(num) => format!("{:.*}", precision, num),
+            StatValue::Scientific(num) => format!("{:.e}", num),
+            StatValue::Percent(share) => format!("{:.1}%", share * 100.0),
+            StatValue::Text(text) => text.clone(),
+        }
+    }
+}
+
+/// Output statistics of a profile as rows of group keys and stat values, shared by all modes
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    pub stats: Vec<Stat>,
+    pub rows: Vec<ReportRow>,
+    /// Single row without group keys, laid out with a statistic per line (number, string and date
+    /// modes)
+    pub transposed: bool,
+    /// Tables after the stats, only the non-empty ones
+    pub sections: Vec<Section>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReportRow {
+    pub group: Vec<String>,
+    /// Column name (csv mode)
+    pub column: Option<String>,
+    /// Values in the order of the statistics
+    pub values: Vec<StatValue>,
+}
+
+impl ReportRow {
+    /// Group keys, then the column in csv mode
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.group.iter().chain(&self.column)
+    }
+}
+
+/// Table printed after the stats
+#[derive(Clone, Debug, PartialEq)]
+pub enum Section {
+    /// Examples of the values that are not numbers, by group keys and column
+    ErrorExamples(Vec<(Vec<String>, Option<String>, Vec<String>)>),
+    /// Bins of the number histograms, by group keys and column
+    Histograms(Vec<(Vec<String>, Option<String>, Vec<HistogramBin>)>),
+    /// Correlations of the pairs of number columns
+    Correlations(Vec<Correlation>),
+}
+
+impl Report {
+    /// Report of the profile rows, with a row per value in the count modes
+    pub fn new(profile: &Profile) -> Self {
+        let rows = profile
+            .rows
+            .iter()
+            .flat_map(|row| {
+                let report_row = |value_count| ReportRow {
+                    group: row.group.clone(),
+                    column: row.column.clone(),
+                    values: profile
+                        .stats
+                        .iter()
+                        .map(|stat| stat.value(row, value_count))
+                        .collect(),
+                };
+                match row.counts.as_ref() {
+                    Some(count_summary) => count_summary
+                        .values
+                        .iter()
+                        .map(|value_count| report_row(Some(value_count)))
+                        .collect(),
+                    None => vec![report_row(None)],
+                }
+            })
+            .collect();
+        Self {
+            stats: profile.stats.clone(),
+            rows,
+            transposed: matches!(profile.mode, Mode::Number | Mode::String | Mode::Date),
+            sections: sections(profile),
+        }
+    }
+
+    /// Number of group key columns, the column included in csv mode
+    pub fn group_length(&self) -> usize {
+        self.rows.first().map_or(0, |row| row.keys().count())
+    }
+}
+
+/// Error examples, histograms and correlations of the profile, if any
+fn sections(profile: &Profile) -> Vec<Section> {
+    let mut sections = Vec::new();
+    let error_examples = number_rows(profile, |number_summary| &number_summary.error_examples);
+    if !error_examples.is_empty() {
+        sections.push(Section::ErrorExamples(error_examples));
+    }
+    let histograms = number_rows(profile, |number_summary| &number_summary.histogram);
+    if !histograms.is_empty() {
+        sections.push(Section::Histograms(histograms));
+    }
+    if !profile.correlations.is_empty() {
+        sections.push(Section::Correlations(profile.correlations.clone()));
+    }
+    sections
+}
+
+/// Group keys and column of the rows with some of the given number summary values
+fn number_rows<T: Clone>(
+    profile: &Profile,
+    values: fn(&NumberSummary) -> &Vec<T>,
+) -> Vec<(Vec<String>, Option<String>, Vec<T>)> {
+    profile
+        .rows
+        .iter()
+        .filter_map(|row| {
+            let values = values(row.number.as_ref()?);
+            (!values.is_empty()).then(|| (row.group.clone(), row.column.clone(), values.clone()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiler::Profiler;
+
+    #[test]
+    fn test_format() {
+        assert_eq!(StatValue::Missing.format(2), "");
+        assert_eq!(StatValue::Count(3).format(2), "3");
+        assert_eq!(StatValue::Number(1.234).format(2), "1.23");
+        assert_eq!(StatValue::Scientific(1500.0).format(2), "1.5e3");
+        assert_eq!(StatValue::Percent(0.125).format(2), "12.5%");
+    }
+
+    #[test]
+    fn test_count_rows() {
+        let profile = Profiler::new(Mode::GroupCount)
+            .profile_records(vec![
+                vec!["x".to_string(), "a".to_string()],
+                vec!["x".to_string(), "b".to_string()],
+                vec!["x".to_string(), "a".to_string()],
+            ])
+            .unwrap();
+        let report = Report::new(&profile);
+        assert!(!report.transposed);
+        assert_eq!(report.group_length(), 1);
+        assert_eq!(
+            report.rows[0].values,
+            [
+                StatValue::Text("a".to_string()),
+                StatValue::Count(2),
+                StatValue::Percent(2.0 / 3.0),
+                StatValue::Percent(2.0 / 3.0)
+            ]
+        );
+        assert_eq!(report.rows.len(), 2);
+    }
+
+    #[test]
+    fn test_sections() {
+        let profile = Profiler::new(Mode::Csv)
+            .error_examples(1)
+            .correlations(true)
+            .profile_records(vec![
+                vec!["a".to_string(), "b".to_string()],
+                vec!["1".to_string(), "2".to_string()],
+                vec!["2".to_string(), "4".to_string()],
+                vec!["x".to_string(), "5".to_string()],
+                vec!["3".to_string(), "7".to_string()],
+            ])
+            .unwrap();
+        let report = Report::new(&profile);
+        assert_eq!(report.sections.len(), 2);
+        assert_eq!(
+            report.sections[0],
+            Section::ErrorExamples(vec![(
+                Vec::new(),
+                Some("a".to_string()),
+                vec!["x".to_string()]
+            )])
+        );
+        assert!(
+            matches!(&report.sections[1], Section::Correlations(correlations) if correlations.len() == 1)
+        );
+
+        let profile = Profiler::new(Mode::Number)
+            .profile_records(vec![vec!["1".to_string()]])
+            .unwrap();
+        assert!(Report::new(&profile).sections.is_empty());
+    }
+}
@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+
+/// Heavy hitters sketch (Space-Saving, Metwally et al.).
+/// Monitors at most `capacity` values. When full, an unmonitored value replaces the value with the
+/// lowest count and inherits (overestimates) that count. Every value occurring more than
+/// `n / capacity` times is guaranteed to be monitored. Exact while the number of distinct values
+/// does not exceed the capacity.
+#[derive(Clone, Debug)]
+pub struct SpaceSaving {
+    capacity: usize,
+    /// Slot of each monitored value in `counters`
+    index: HashMap<String, usize>,
+    counters: Vec<Counter>,
+    /// Binary min-heap of the slots on their counts, so that incrementing a count or replacing the
+    /// lowest one takes O(log capacity)
+    heap: Vec<usize>,
+    /// Position of each slot in `heap`
+    positions: Vec<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Counter {
+    value: String,
+    count: usize,
+}
+
+impl SpaceSaving {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            index: HashMap::new(),
+            counters: Vec::new(),
+            heap: Vec::new(),
+            positions: Vec::new(),
+        }
+    }
+    pub fn add(&mut self, value: &str) {
+        if let Some(&slot) = self.index.get(value) {
+            self.counters[slot].count += 1;
+            self.sift_down(self.positions[slot]);
+        } else if self.counters.len() < self.capacity {
+            let slot = self.counters.len();
+            self.index.insert(value.to_string(), slot);
+            self.counters.push(Counter {
+                value: value.to_string(),
+                count: 1,
+            });
+            self.heap.push(slot);
+            self.positions.push(slot);
+            self.sift_up(self.heap.len() - 1);
+        } else if let Some(&slot) = self.heap.first() {
+            let counter = &mut self.counters[slot];
+            self.index.remove(&counter.value);
+            self.index.insert(value.to_string(), slot);
+            counter.value = value.to_string();
+            counter.count += 1;
+            self.sift_down(0);
+        }
+    }
+    /// Combine with another sketch by summing the counts and keeping the `capacity` highest. A
+    /// value missing from a sketch is credited with its lowest count, the most it can have
+    /// occurred there, so the counts stay overestimates (mergeable summaries, Agarwal et al.).
+    pub fn merge(&mut self, other: &Self) {
+        let min_count = self.min_count();
+        let other_min_count = other.min_count();
+        for counter in self.counters.iter_mut() {
+            if !other.index.contains_key(&counter.value) {
+                counter.count += other_min_count;
+            }
+        }
+        for counter in other.counters.iter() {
+            if let Some(&slot) = self.index.get(&counter.value) {
+                self.counters[slot].count += counter.count;
+            } else {
+                self.index
+                    .insert(counter.value.clone(), self.counters.len());
+                self.counters.push(Counter {
+                    value: counter.value.clone(),
+                    count: counter.count + min_count,
+                });
+            }
+        }
+        // Rebuild the slots in heap order, the lowest counts first
+        self.counters
+            .sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+        self.counters.truncate(self.capacity);
+        self.counters.reverse();
+        self.index = self
+            .counters
+            .iter()
+            .enumerate()
+            .map(|(slot, counter)| (counter.value.clone(), slot))
+            .collect();
+        self.heap = (0..self.counters.len()).collect();
+        self.positions = self.heap.clone();
+    }
+    /// The `n` most frequent values with their (possibly overestimated) counts, most frequent first
+    pub fn top(&self, n: usize) -> Vec<(String, usize)> {
+        let mut counters: Vec<&Counter> = self.counters.iter().collect();
+        counters.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+        counters
+            .into_iter()
+            .take(n)
+            .map(|counter| (counter.value.clone(), counter.count))
+            .collect()
+    }
+
+    /// Most occurrences of an unmonitored value: 0 until full, then the lowest count
+    fn min_count(&self) -> usize {
+        if self.counters.len() < self.capacity {
+            0
+        } else {
+            self.heap
+                .first()
+                .map_or(0, |&slot| self.counters[slot].count)
+        }
+    }
+
+    fn count(&self, position: usize) -> usize {
+        self.counters[self.heap[position]].count
+    }
+
+    /// Move the slot at the heap position up while its count is below its parent's
+    fn sift_up(&mut self, mut position: usize) {
+        while position > 0 {
+            let parent = (position - 1) / 2;
+            if self.count(parent) <= self.count(position) {
+                break;
+            }
+            self.swap(parent, position);
+            position = parent;
+        }
+    }
+
+    /// Move the slot at the heap position down while its count is above a child's
+    fn sift_down(&mut self, mut position: usize) {
+        loop {
+            let lowest = [2 * position + 1, 2 * position + 2]
+                .into_iter()
+                .filter(|&child| child < self.heap.len())
+                .fold(position, |lowest, child| {
+                    if self.count(child) < self.count(lowest) {
+                        child
+                    } else {
+                        lowest
+                    }
+                });
+            if lowest == position {
+                return;
+            }
+            self.swap(lowest, position);
+            position = lowest;
+        }
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        self.positions[self.heap[a]] = a;
+        self.positions[self.heap[b]] = b;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_within_capacity() {
+        let mut sketch = SpaceSaving::new(10);
+        for value in ["a", "b", "a", "c", "a", "b"] {
+            sketch.add(value);
+        }
+        assert_eq!(
+            sketch.top(2),
+            vec![("a".to_string(), 3), ("b".to_string(), 2)]
+        );
+        assert_eq!(sketch.top(5).len(), 3);
+    }
+
+    #[test]
+    fn test_merge() {
+        let (mut left, mut right) = (SpaceSaving::new(2), SpaceSaving::new(2));
+        for value in ["a", "b", "a"] {
+            left.add(value);
+        }
+        for value in ["b", "c", "b", "c", "c"] {
+            right.add(value);
+        }
+        left.merge(&right);
+        // a is credited with the lowest count of the right sketch, c with the one of the left
+        assert_eq!(
+            left.top(3),
+            vec![("a".to_string(), 4), ("c".to_string(), 4)]
+        );
+        left.add("b");
+        assert_eq!(left.top(1), vec![("b".to_string(), 5)]);
+
+        let (mut left, right) = (SpaceSaving::new(3), SpaceSaving::new(3));
+        left.add("a");
+        left.merge(&right);
+        assert_eq!(left.top(3), vec![("a".to_string(), 1)]);
+    }
+
+    #[test]
+    fn test_merge_different_top_values() {
+        let left_values = "xxxxxyyzpqxr";
+        let right_values = "wwwwwyvyyvuxt";
+        let (mut left, mut right) = (SpaceSaving::new(3), SpaceSaving::new(3));
+        for value in left_values.chars() {
+            left.add(&value.to_string());
+        }
+        for value in right_values.chars() {
+            right.add(&value.to_string());
+        }
+        left.merge(&right);
+        let top = left.top(3);
+        assert_eq!(top.len(), 3);
+        for (value, count) in top {
+            let occurrences = left_values
+                .chars()
+                .chain(right_values.chars())
+                .filter(|c| c.to_string() == value)
+                .count();
+            assert!(count >= occurrences, "{} {} {}", value, count, occurrences);
+        }
+    }
+
+    #[test]
+    fn test_heavy_hitter_survives_eviction() {
+        let mut sketch = SpaceSaving::new(4);
+        for i in 0..1_000 {
+            sketch.add("heavy");
+            sketch.add(&format!("noise{}", i));
+        }
+        let top = sketch.top(1);
+        assert_eq!(top[0].0, "heavy");
+        assert!(top[0].1 >= 1_000);
+        assert_eq!(sketch.counters.len(), 4);
+    }
+
+    #[test]
+    fn test_evicts_lowest_count() {
+        let mut sketch = SpaceSaving::new(3);
+        for value in ["a", "a", "a", "b", "b", "c", "a", "b", "d"] {
+            sketch.add(value);
+        }
+        // d replaced c, the lowest count
+        assert_eq!(
+            sketch.top(3),
+            vec![
+                ("a".to_string(), 4),
+                ("b".to_string(), 3),
+                ("d".to_string(), 2)
+            ]
+        );
+        for (position, &slot) in sketch.heap.iter().enumerate() {
+            assert_eq!(sketch.positions[slot], position);
+            assert_eq!(sketch.index[&sketch.counters[slot].value], slot);
+            if position > 0 {
+                assert!(sketch.count((position - 1) / 2) <= sketch.count(position));
+            }
+        }
+    }
+}
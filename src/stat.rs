@@ -0,0 +1,521 @@
+use crate::mode::{format_span, Mode};
+use crate::profile::{NumberSummary, ProfileRow, ValueCount};
+use crate::report::StatValue;
+use clap::ValueEnum;
+use itertools::Itertools;
+
+/// Part of a profile row a statistic is read from
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Section {
+    /// Inferred type (csv mode)
+    Type,
+    String,
+    Number,
+    /// Length of the string values
+    Length,
+    Date,
+    /// Frequency table (count modes)
+    Count,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Field {
+    Type,
+    Count,
+    Empty,
+    Error,
+    Cardinality,
+    Min,
+    Max,
+    Sum,
+    Mean,
+    StdDev,
+    Variance,
+    Skewness,
+    Kurtosis,
+    Cv,
+    StdError,
+    CiLower,
+    CiUpper,
+    Median,
+    Iqr,
+    Span,
+    Formats,
+    Value,
+    Percent,
+    Cumulative,
+    /// Percentile (0-100), named like `p95`
+    Percentile(f64),
+    /// Most frequent value at a 1-based position, named like `top_1`
+    Top(usize),
+}
+
+/// Registry of the named statistics of each section, with their name and table title
+const NUMBER_FIELDS: &[(Field, &str, &str)] = &[
+    (Field::Count, "count", "Count"),
+    (Field::Empty, "empty", "Empty"),
+    (Field::Error, "error", "Error"),
+    (Field::Min, "min", "Min"),
+    (Field::Max, "max", "Max"),
+    (Field::Sum, "sum", "Sum"),
+    (Field::Mean, "mean", "Mean"),
+    (Field::StdDev, "stddev", "StdDev"),
+    (Field::Variance, "variance", "Variance"),
+    (Field::Skewness, "skewness", "Skewness"),
+    (Field::Kurtosis, "kurtosis", "Kurtosis"),
+    (Field::Cv, "cv", "CV"),
+    (Field::StdError, "std_error", "StdErr"),
+    (Field::CiLower, "ci_lower", "CI Lower"),
+    (Field::CiUpper, "ci_upper", "CI Upper"),
+    (Field::Median, "median", "Median"),
+    (Field::Iqr, "iqr", "IQR"),
+];
+const STRING_FIELDS: &[(Field, &str, &str)] = &[
+    (Field::Count, "count", "Count"),
+    (Field::Empty, "empty", "Empty"),
+    (Field::Error, "error", "Error"),
+    (Field::Cardinality, "cardinality", "Cardinality"),
+    (Field::Min, "min", "Min"),
+    (Field::Max, "max", "Max"),
+];
+const DATE_FIELDS: &[(Field, &str, &str)] = &[
+    (Field::Count, "count", "Count"),
+    (Field::Empty, "empty", "Empty"),
+    (Field::Error, "error", "Error"),
+    (Field::Min, "min", "Min"),
+    (Field::Max, "max", "Max"),
+    (Field::Span, "span", "Span"),
+    (Field::Formats, "formats", "Formats"),
+];
+const COUNT_FIELDS: &[(Field, &str, &str)] = &[
+    (Field::Value, "value", "Value"),
+    (Field::Count, "count", "Count"),
+    (Field::Percent, "percent", "Percent"),
+    (Field::Cumulative, "cumulative_percent", "Cumulative"),
+];
+const TYPE_FIELDS: &[(Field, &str, &str)] = &[(Field::Type, "type", "Type")];
+
+/// Moment stats, output after the standard deviation when requested
+const MOMENTS: [&str; 7] = [
+    "variance",
+    "skewness",
+    "kurtosis",
+    "cv",
+    "std_error",
+    "ci_lower",
+    "ci_upper",
+];
+
+impl Section {
+    fn fields(self) -> &'static [(Field, &'static str, &'static str)] {
+        match self {
+            Section::Type => TYPE_FIELDS,
+            Section::String => STRING_FIELDS,
+            Section::Number | Section::Length => NUMBER_FIELDS,
+            Section::Date => DATE_FIELDS,
+            Section::Count => COUNT_FIELDS,
+        }
+    }
+    /// Name and title prefix of the statistics of the section, when they are not the main ones of
+    /// the mode
+    fn prefix(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            Section::Type | Section::Count => None,
+            Section::String => Some(("string_", "String ")),
+            Section::Number => Some(("number_", "Number ")),
+            Section::Length => Some(("length_", "Length ")),
+            Section::Date => Some(("date_", "Date ")),
+        }
+    }
+    /// Statistic of the section by unprefixed name, with its title
+    fn field(self, name: &str) -> Option<(Field, String)> {
+        if let Some(&(field, _, title)) = self.fields().iter().find(|field| field.1 == name) {
+            return Some((field, title.to_string()));
+        }
+        match self {
+            Section::Number | Section::Length => {
+                let percentile = name.strip_prefix('p')?.parse::<f64>().ok()?;
+                (0.0..=100.0)
+                    .contains(&percentile)
+                    .then(|| (Field::Percentile(percentile), format!("P{}", percentile)))
+            }
+            Section::String => {
+                let position = name.strip_prefix("top_")?.parse::<usize>().ok()?;
+                (position > 0).then(|| (Field::Top(position), format!("Top {}", position)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Main section of the mode, whose statistics are also named without prefix, and all sections
+fn sections(mode: Mode) -> (Section, &'static [Section]) {
+    match mode {
+        Mode::Number | Mode::GroupNumber => (Section::Number, &[Section::Number]),
+        Mode::String | Mode::GroupString => (Section::String, &[Section::String, Section::Length]),
+        Mode::Date => (Section::Date, &[Section::Date]),
+        Mode::Count | Mode::GroupCount => (Section::Count, &[Section::Count]),
+        Mode::Csv => (
+            Section::String,
+            &[
+                Section::Type,
+                Section::String,
+                Section::Number,
+                Section::Date,
+                Section::Length,
+            ],
+        ),
+    }
+}
+
+/// Named statistic of the table and csv output, like `mean`, `p95` or `length_max`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stat {
+    /// Name, as the csv header
+    pub name: String,
+    /// Table header
+    pub title: String,
+    section: Section,
+    field: Field,
+}
+
+impl Stat {
+    /// Statistic of the mode by name. The statistics of a section other than the main one of the
+    /// mode are prefixed with the section, like `length_mean` or `number_p95` in csv mode.
+    pub fn parse(name: &str, mode: Mode) -> Result<Self, String> {
+        let (main, sections) = sections(mode);
+        let stat = |section: Section, (field, title): (Field, String)| Self {
+            name: name.to_string(),
+            title,
+            section,
+            field,
+        };
+        for &section in sections {
+            let prefixed = section.prefix().and_then(|(prefix, title_prefix)| {
+                let (field, title) = section.field(name.strip_prefix(prefix)?)?;
+                Some((field, format!("{}{}", title_prefix, title)))
+            });
+            if let Some(field) = prefixed {
+                return Ok(stat(section, field));
+            }
+        }
+        sections
+            .iter()
+            .filter(|section| **section == main || section.prefix().is_none())
+            .find_map(|&section| Some(stat(section, section.field(name)?)))
+            .ok_or_else(|| {
+                format!(
+                    "{} is not a statistic of {} mode, like {}",
+                    name,
+                    mode.to_possible_value().unwrap().get_name(),
+                    Self::names(mode).join(", ")
+                )
+            })
+    }
+
+    /// Names of the statistics of the mode
+    pub fn names(mode: Mode) -> Vec<String> {
+        let (main, sections) = sections(mode);
+        sections
+            .iter()
+            .flat_map(|&section| {
+                let prefix = match section.prefix() {
+                    Some((prefix, _)) if section != main => prefix,
+                    _ => "",
+                };
+                let patterns: &[&str] = match section {
+                    Section::Number | Section::Length => &["p<percentile>"],
+                    Section::String => &["top_<position>"],
+                    _ => &[],
+                };
+                section
+                    .fields()
+                    .iter()
+                    .map(|field| field.1)
+                    .chain(patterns.iter().copied())
+                    .map(move |name| format!("{}{}", prefix, name))
+            })
+            .collect()
+    }
+
+    /// Statistics output by default in the mode, with the requested percentiles, number of most
+    /// frequent values and moments
+    pub fn defaults(mode: Mode, percentiles: &[f64], top: usize, moments: bool) -> Vec<Self> {
+        let number = |prefix: &str| -> Vec<String> {
+            ["empty", "error", "min", "max", "sum", "mean", "stddev"]
+                .into_iter()
+                .chain(MOMENTS.into_iter().filter(|_| moments))
+                .chain(["median", "iqr"])
+                .map(str::to_string)
+                .chain(
+                    percentiles
+                        .iter()
+                        .map(|percentile| format!("p{}", percentile)),
+                )
+                .map(|name| format!("{}{}", prefix, name))
+                .collect()
+        };
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect_vec();
+        let tops = (1..=top).map(|position| format!("top_{}", position));
+        let lengths = ["length_min", "length_max", "length_mean", "length_stddev"];
+        let names: Vec<String> = match mode {
+            Mode::Number | Mode::GroupNumber => {
+                names(&["count"]).into_iter().chain(number("")).collect()
+            }
+            Mode::String | Mode::GroupString => {
+                names(&["count", "empty", "cardinality", "string_min", "string_max"])
+                    .into_iter()
+                    .chain(names(&lengths))
+                    .chain(tops)
+                    .collect()
+            }
+            Mode::Date => names(&["count", "empty", "error", "min", "max", "span", "formats"]),
+            Mode::Count | Mode::GroupCount => {
+                names(&["value", "count", "percent", "cumulative_percent"])
+            }
+            Mode::Csv => names(&["type", "count", "cardinality"])
+                .into_iter()
+                .chain(names(&["string_empty", "string_min", "string_max"]))
+                .chain(number("number_"))
+                .chain(names(&["date_min", "date_max", "date_span"]))
+                .chain(names(&lengths))
+                .chain(tops)
+                .collect(),
+        };
+        names
+            .iter()
+            .map(|name| Self::parse(name, mode).unwrap())
+            .collect()
+    }
+
+    /// Requested percentile (0-100), for a percentile statistic
+    pub fn percentile(&self) -> Option<f64> {
+        match self.field {
+            Field::Percentile(percentile) => Some(percentile),
+            _ => None,
+        }
+    }
+
+    /// Position of the most frequent value, for a top value statistic
+    pub fn top_position(&self) -> Option<usize> {
+        match self.field {
+            Field::Top(position) => Some(position),
+            _ => None,
+        }
+    }
+
+    /// Whether the statistic is text to align left (the values of the count modes)
+    pub fn left_aligned(&self) -> bool {
+        self.field == Field::Value
+    }
+
+    /// Value of the statistic for a row and, in the count modes, one of its values
+    pub fn value(&self, row: &ProfileRow, value_count: Option<&ValueCount>) -> StatValue {
+        let value = match self.section {
+            Section::Type => row.inferred_type.as_ref().map(|type_summary| {
+                StatValue::Text(format!(
+                    "{} ({:.1}%)",
+                    type_summary.value_type,
+                    type_summary.share * 100.0
+                ))
+            }),
+            Section::String => row.string.as_ref().and_then(|summary| match self.field {
+                Field::Count => Some(StatValue::Count(summary.count)),
+                Field::Empty => Some(StatValue::Count(summary.empty)),
+                Field::Error => Some(StatValue::Count(summary.error)),
+                Field::Cardinality => Some(StatValue::Count(summary.cardinality)),
+                Field::Min => summary.min.clone().map(StatValue::Text),
+                Field::Max => summary.max.clone().map(StatValue::Text),
+                Field::Top(position) => summary.top.get(position - 1).map(|top_value| {
+                    StatValue::Text(format!(
+                        "{} ({}, {:.1}%)",
+                        top_value.value,
+                        top_value.count,
+                        top_value.share * 100.0
+                    ))
+                }),
+                _ => None,
+            }),
+            Section::Number => row
+                .number
+                .as_ref()
+                .and_then(|summary| number_value(self.field, summary, false)),
+            Section::Length => row
+                .length
+                .as_ref()
+                .and_then(|summary| number_value(self.field, summary, true)),
+            Section::Date => row.date.as_ref().and_then(|summary| match self.field {
+                Field::Count => Some(StatValue::Count(summary.count)),
+                Field::Empty => Some(StatValue::Count(summary.empty)),
+                Field::Error => Some(StatValue::Count(summary.error)),
+                Field::Min => summary.min.clone().map(StatValue::Text),
+                Field::Max => summary.max.clone().map(StatValue::Text),
+                Field::Span => summary.span.map(|span| StatValue::Text(format_span(span))),
+                Field::Formats => Some(StatValue::Text(
+                    summary
+                        .formats
+                        .iter()
+                        .map(|format_count| {
+                            format!("{} ({})", format_count.format, format_count.count)
+                        })
+                        .join(", "),
+                )),
+                _ => None,
+            }),
+            Section::Count => value_count.and_then(|value_count| match self.field {
+                Field::Value => Some(StatValue::Text(value_count.value.clone())),
+                Field::Count => Some(StatValue::Count(value_count.count)),
+                Field::Percent => Some(StatValue::Percent(value_count.share)),
+                Field::Cumulative => Some(StatValue::Percent(value_count.cumulative_share)),
+                _ => None,
+            }),
+        };
+        value.unwrap_or(StatValue::Missing)
+    }
+}
+
+/// Value of a number statistic, with whole min and max for the lengths. Undefined statistics, like
+/// the mean without numbers or the skewness of a constant, are missing.
+fn number_value(field: Field, summary: &NumberSummary, lengths: bool) -> Option<StatValue> {
+    let interval = summary.mean_interval.as_ref();
+    let numbers = summary.count > 0;
+    let value = match field {
+        Field::Count => return Some(StatValue::Count(summary.count)),
+        Field::Empty => return Some(StatValue::Count(summary.empty)),
+        Field::Error => return Some(StatValue::Count(summary.error)),
+        Field::Sum => return Some(StatValue::Scientific(summary.sum)),
+        Field::Min | Field::Max if lengths => {
+            let bound = if field == Field::Min {
+                summary.min
+            } else {
+                summary.max
+            };
+            return bound.map(|bound| StatValue::Count(bound as usize));
+        }
+        Field::Min => summary.min,
+        Field::Max => summary.max,
+        Field::Mean => numbers.then_some(summary.mean),
+        Field::StdDev => numbers.then_some(summary.stddev),
+        Field::Variance => numbers.then_some(summary.variance),
+        Field::Skewness => summary.skewness,
+        Field::Kurtosis => summary.kurtosis,
+        Field::Cv => summary.cv,
+        Field::StdError => summary.std_error,
+        Field::CiLower => interval.map(|interval| interval.lower),
+        Field::CiUpper => interval.map(|interval| interval.upper),
+        Field::Median => summary.median,
+        Field::Iqr => summary.iqr,
+        Field::Percentile(percentile) => summary
+            .percentiles
+            .iter()
+            .find(|value| value.percentile == percentile)
+            .and_then(|value| value.value),
+        _ => return None,
+    };
+    value.map(StatValue::Number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiler::Profiler;
+
+    fn names(stats: &[Stat]) -> Vec<&str> {
+        stats.iter().map(|stat| stat.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let stat = Stat::parse("p95", Mode::Number).unwrap();
+        assert_eq!(
+            (stat.title.as_str(), stat.percentile()),
+            ("P95", Some(95.0))
+        );
+        assert_eq!(
+            Stat::parse("number_mean", Mode::Number).unwrap().title,
+            "Number Mean"
+        );
+        assert_eq!(
+            Stat::parse("length_p90", Mode::Csv).unwrap().title,
+            "Length P90"
+        );
+        assert_eq!(
+            Stat::parse("count", Mode::Csv).unwrap().section,
+            Section::String
+        );
+        assert_eq!(Stat::parse("type", Mode::Csv).unwrap().title, "Type");
+        assert_eq!(
+            Stat::parse("top_2", Mode::String).unwrap().top_position(),
+            Some(2)
+        );
+        assert!(Stat::parse("value", Mode::GroupCount)
+            .unwrap()
+            .left_aligned());
+        assert!(Stat::parse("mean", Mode::Csv).is_err());
+        assert!(Stat::parse("type", Mode::Number).is_err());
+        assert!(Stat::parse("p101", Mode::Number).is_err());
+        let error = Stat::parse("top_0", Mode::String).unwrap_err();
+        assert!(error.contains("string mode, like count, empty"));
+        assert!(error.contains("length_p<percentile>"));
+    }
+
+    #[test]
+    fn test_defaults() {
+        assert_eq!(
+            names(&Stat::defaults(Mode::Number, &[95.0], 0, true)),
+            [
+                "count",
+                "empty",
+                "error",
+                "min",
+                "max",
+                "sum",
+                "mean",
+                "stddev",
+                "variance",
+                "skewness",
+                "kurtosis",
+                "cv",
+                "std_error",
+                "ci_lower",
+                "ci_upper",
+                "median",
+                "iqr",
+                "p95"
+            ]
+        );
+        let csv = Stat::defaults(Mode::Csv, &[], 1, false);
+        assert_eq!(csv.len(), 23);
+        assert_eq!(csv[6].title, "Number Empty");
+        assert_eq!(csv[22].title, "Top 1");
+        for mode in Mode::value_variants() {
+            assert!(!Stat::defaults(*mode, &[], 0, false).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_missing_values() {
+        let profile = Profiler::new(Mode::Number)
+            .moments(true)
+            .profile_records(vec![vec!["3".to_string()], vec!["3".to_string()]])
+            .unwrap();
+        let value = |name| {
+            Stat::parse(name, Mode::Number)
+                .unwrap()
+                .value(&profile.rows[0], None)
+        };
+        assert_eq!(value("mean"), StatValue::Number(3.0));
+        assert_eq!(value("skewness"), StatValue::Missing);
+        assert_eq!(value("kurtosis"), StatValue::Missing);
+
+        let profile = Profiler::new(Mode::Csv)
+            .profile_records(vec![vec!["a".to_string()], vec!["x".to_string()]])
+            .unwrap();
+        for name in ["number_min", "number_mean", "number_median", "number_iqr"] {
+            let stat = Stat::parse(name, Mode::Csv).unwrap();
+            assert_eq!(stat.value(&profile.rows[0], None), StatValue::Missing);
+        }
+        let stat = Stat::parse("length_min", Mode::Csv).unwrap();
+        assert_eq!(stat.value(&profile.rows[0], None), StatValue::Count(1));
+    }
+}
@@ -1,11 +1,18 @@
+use crate::space_saving::SpaceSaving;
 use cardinality_estimator::CardinalityEstimator;
-use stats::MinMax;
+use stats::{Commute, MinMax};
+
+/// Number of monitored values per requested top value, for accuracy of the heavy hitters
+const TOP_CAPACITY_FACTOR: usize = 10;
+const TOP_MIN_CAPACITY: usize = 100;
 
 pub struct StringStats {
     empty_count: usize,
     error_count: usize,
     min_max: MinMax<String>,
     cardinality_estimator: CardinalityEstimator<String>,
+    top: usize,
+    space_saving: Option<SpaceSaving>,
 }
 
 impl StringStats {
@@ -15,27 +22,57 @@ impl StringStats {
             error_count: 0,
             min_max: MinMax::new(),
             cardinality_estimator: CardinalityEstimator::new(),
+            top: 0,
+            space_saving: None,
+        }
+    }
+    /// Also track the `top` most frequent values (no tracking for 0)
+    pub fn with_top(top: usize) -> Self {
+        let space_saving =
+            (top > 0).then(|| SpaceSaving::new((top * TOP_CAPACITY_FACTOR).max(TOP_MIN_CAPACITY)));
+        Self {
+            top,
+            space_saving,
+            ..Self::new()
         }
     }
     pub fn add(&mut self, string: String) {
         self.cardinality_estimator.insert(&string);
+        if let Some(space_saving) = self.space_saving.as_mut() {
+            space_saving.add(&string);
+        }
         self.min_max.add(string);
     }
+    /// Combine with stats of another part of the input, as if all values were added to this one
+    pub fn merge(&mut self, other: &Self) {
+        self.empty_count += other.empty_count;
+        self.error_count += other.error_count;
+        self.min_max.merge(other.min_max.clone());
+        self.cardinality_estimator
+            .merge(&other.cardinality_estimator);
+        match (self.space_saving.as_mut(), other.space_saving.as_ref()) {
+            (Some(space_saving), Some(other_space_saving)) => {
+                space_saving.merge(other_space_saving)
+            }
+            (None, Some(_)) => {
+                self.top = other.top;
+                self.space_saving = other.space_saving.clone();
+            }
+            _ => {}
+        }
+    }
     pub fn add_empty(&mut self) {
         self.empty_count += 1;
     }
     pub fn add_error(&mut self) {
         self.error_count += 1;
     }
-    #[allow(dead_code)]
     pub fn count(&self) -> usize {
         self.min_max.len()
     }
-    #[allow(dead_code)]
     pub fn empty_count(&self) -> usize {
         self.empty_count
     }
-    #[allow(dead_code)]
     pub fn error_count(&self) -> usize {
         self.error_count
     }
@@ -48,6 +85,28 @@ impl StringStats {
     pub fn cardinality(&self) -> usize {
         self.cardinality_estimator.estimate()
     }
+    /// Most frequent values with their approximate count and share of the non-empty count
+    pub fn top(&self) -> Vec<(String, usize, f64)> {
+        let count = self.count();
+        self.space_saving
+            .as_ref()
+            .map(|space_saving| {
+                space_saving
+                    .top(self.top)
+                    .into_iter()
+                    .map(|(value, value_count)| {
+                        (value, value_count, value_count as f64 / count as f64)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Default for StringStats {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[cfg(test)]
@@ -80,6 +139,34 @@ mod tests {
         assert_eq!(stats.empty_count(), 1);
     }
 
+    #[test]
+    fn test_merge() {
+        let values = ["b", "a", "c", "a", "d", "a", "b"];
+        let mut all = StringStats::with_top(2);
+        let (mut left, mut right) = (StringStats::with_top(2), StringStats::with_top(2));
+        for (index, value) in values.iter().enumerate() {
+            all.add(value.to_string());
+            if index % 2 == 0 {
+                left.add(value.to_string());
+            } else {
+                right.add(value.to_string());
+            }
+        }
+        all.add_empty();
+        right.add_empty();
+        all.add_error();
+        left.add_error();
+
+        left.merge(&right);
+        assert_eq!(left.count(), all.count());
+        assert_eq!(left.empty_count(), all.empty_count());
+        assert_eq!(left.error_count(), all.error_count());
+        assert_eq!(left.min(), all.min());
+        assert_eq!(left.max(), all.max());
+        assert_eq!(left.cardinality(), all.cardinality());
+        assert_eq!(left.top(), all.top());
+    }
+
     #[test]
     fn test_min_max() {
         let mut stats = StringStats::new();
@@ -135,6 +222,19 @@ mod string_stats_tests {
         assert_eq!(stats.max(), Some("b".to_string()));
     }
 
+    #[test]
+    fn test_top() {
+        let mut stats = StringStats::with_top(2);
+        for value in ["a", "b", "a", "c", "a", "b"] {
+            stats.add(value.to_string());
+        }
+        assert_eq!(
+            stats.top(),
+            vec![("a".to_string(), 3, 0.5), ("b".to_string(), 2, 1.0 / 3.0)]
+        );
+        assert_eq!(StringStats::new().top(), vec![]);
+    }
+
     #[test]
     fn test_cardinality() {
         let mut stats = StringStats::new();
@@ -0,0 +1,281 @@
+/// Bounded memory quantile sketch (merging t-digest, Dunning & Ertl).
+/// Values are buffered and periodically merged into centroids whose size is bounded by
+/// `4 * n * q * (1 - q) / compression`, so the tails stay accurate while the total number of
+/// centroids stays in the order of the compression. Small inputs are kept exact.
+#[derive(Clone, Debug)]
+pub struct TDigest {
+    compression: f64,
+    centroids: Vec<Centroid>,
+    buffer: Vec<f64>,
+    min: f64,
+    max: f64,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+const DEFAULT_COMPRESSION: f64 = 100.0;
+const BUFFER_FACTOR: usize = 5;
+
+impl TDigest {
+    pub fn new() -> Self {
+        Self::with_compression(DEFAULT_COMPRESSION)
+    }
+    pub fn with_compression(compression: f64) -> Self {
+        Self {
+            compression,
+            centroids: Vec::new(),
+            buffer: Vec::new(),
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+    pub fn add(&mut self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.buffer.push(value);
+        if self.buffer.len() >= BUFFER_FACTOR * self.compression as usize {
+            self.centroids = self.compressed();
+            self.buffer.clear();
+        }
+    }
+    /// Combine with another digest, as if all values were added to this one
+    pub fn merge(&mut self, other: &Self) {
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.centroids.extend_from_slice(&other.centroids);
+        self.buffer.extend_from_slice(&other.buffer);
+        if !other.centroids.is_empty()
+            || self.buffer.len() >= BUFFER_FACTOR * self.compression as usize
+        {
+            self.centroids = self.compressed();
+            self.buffer.clear();
+        }
+    }
+    #[allow(dead_code)]
+    pub fn count(&self) -> f64 {
+        self.centroids.iter().map(|c| c.weight).sum::<f64>() + self.buffer.len() as f64
+    }
+    /// Estimate the value at quantile `q` (0.0..=1.0), None when no values were added
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        let centroids = if self.buffer.is_empty() {
+            self.centroids.clone()
+        } else {
+            self.compressed()
+        };
+        let first = centroids.first()?;
+        let last = centroids.last()?;
+        let total: f64 = centroids.iter().map(|c| c.weight).sum();
+        let target = q.clamp(0.0, 1.0) * total;
+
+        if target <= first.weight / 2.0 {
+            return Some(interpolate(
+                self.min,
+                first.mean,
+                target / (first.weight / 2.0),
+            ));
+        }
+        if target >= total - last.weight / 2.0 {
+            return Some(interpolate(
+                last.mean,
+                self.max,
+                (target - (total - last.weight / 2.0)) / (last.weight / 2.0),
+            ));
+        }
+
+        let mut cumulative = 0.0;
+        for pair in centroids.windows(2) {
+            let (left, right) = (pair[0], pair[1]);
+            let left_center = cumulative + left.weight / 2.0;
+            let right_center = cumulative + left.weight + right.weight / 2.0;
+            if target <= right_center {
+                return Some(interpolate(
+                    left.mean,
+                    right.mean,
+                    (target - left_center) / (right_center - left_center),
+                ));
+            }
+            cumulative += left.weight;
+        }
+        Some(last.mean)
+    }
+
+    /// Estimate the number of values below `value`, exact when no values were merged into
+    /// centroids yet
+    pub fn rank(&self, value: f64) -> f64 {
+        let centroids = if self.buffer.is_empty() {
+            self.centroids.clone()
+        } else {
+            self.compressed()
+        };
+        if centroids.iter().all(|c| c.weight == 1.0) {
+            return centroids.iter().filter(|c| c.mean < value).count() as f64;
+        }
+        let total: f64 = centroids.iter().map(|c| c.weight).sum();
+        if value <= self.min {
+            return 0.0;
+        }
+        if value > self.max {
+            return total;
+        }
+
+        // Piecewise linear between the min, the centroid centers and the max
+        let mut points = vec![(self.min, 0.0)];
+        let mut cumulative = 0.0;
+        for centroid in &centroids {
+            points.push((centroid.mean, cumulative + centroid.weight / 2.0));
+            cumulative += centroid.weight;
+        }
+        points.push((self.max, total));
+        points
+            .windows(2)
+            .find(|pair| value <= pair[1].0)
+            .map_or(total, |pair| {
+                let ((from_value, from_rank), (to_value, to_rank)) = (pair[0], pair[1]);
+                if to_value > from_value {
+                    interpolate(
+                        from_rank,
+                        to_rank,
+                        (value - from_value) / (to_value - from_value),
+                    )
+                } else {
+                    from_rank
+                }
+            })
+    }
+
+    fn compressed(&self) -> Vec<Centroid> {
+        let mut all: Vec<Centroid> = self
+            .centroids
+            .iter()
+            .copied()
+            .chain(
+                self.buffer
+                    .iter()
+                    .map(|&mean| Centroid { mean, weight: 1.0 }),
+            )
+            .collect();
+        all.sort_by(|a, b| a.mean.total_cmp(&b.mean));
+
+        let total: f64 = all.iter().map(|c| c.weight).sum();
+        let mut result: Vec<Centroid> = Vec::with_capacity(all.len());
+        let mut weight_so_far = 0.0;
+        for centroid in all {
+            if let Some(current) = result.last_mut() {
+                let combined = current.weight + centroid.weight;
+                let q = (weight_so_far + combined / 2.0) / total;
+                if combined <= 4.0 * total * q * (1.0 - q) / self.compression {
+                    current.mean += (centroid.mean - current.mean) * centroid.weight / combined;
+                    current.weight = combined;
+                    continue;
+                }
+                weight_so_far += current.weight;
+            }
+            result.push(centroid);
+        }
+        result
+    }
+}
+
+impl Default for TDigest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn interpolate(from: f64, to: f64, fraction: f64) -> f64 {
+    from + (to - from) * fraction.clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty() {
+        let digest = TDigest::new();
+        assert_eq!(digest.quantile(0.5), None);
+        assert_eq!(digest.count(), 0.0);
+    }
+
+    #[test]
+    fn test_exact_for_small_input() {
+        let mut digest = TDigest::new();
+        for value in [4.0, 1.0, 3.0, 2.0] {
+            digest.add(value);
+        }
+        assert_eq!(digest.quantile(0.0), Some(1.0));
+        assert_eq!(digest.quantile(0.5), Some(2.5));
+        assert_eq!(digest.quantile(1.0), Some(4.0));
+    }
+
+    #[test]
+    fn test_rank() {
+        let mut digest = TDigest::new();
+        for value in [4.0, 1.0, 3.0, 2.0] {
+            digest.add(value);
+        }
+        assert_eq!(digest.rank(1.0), 0.0);
+        assert_eq!(digest.rank(2.5), 2.0);
+        for value in 0..10_000 {
+            digest.add(value as f64);
+        }
+        assert_eq!(digest.rank(-1.0), 0.0);
+        assert!((digest.rank(5_000.0) - 5_000.0).abs() < 50.0);
+        assert_eq!(digest.rank(10_000.0), 10_004.0);
+    }
+
+    #[test]
+    fn test_merge() {
+        let (mut left, mut right, mut all) = (TDigest::new(), TDigest::new(), TDigest::new());
+        for value in 0..10_000 {
+            if value % 3 == 0 {
+                left.add(value as f64);
+            } else {
+                right.add(value as f64);
+            }
+            all.add(value as f64);
+        }
+        left.merge(&right);
+        assert_eq!(left.count(), 10_000.0);
+        assert_eq!(left.quantile(0.0), Some(0.0));
+        assert_eq!(left.quantile(1.0), Some(9_999.0));
+        for q in [0.1, 0.5, 0.9] {
+            let (merged, sequential) = (left.quantile(q).unwrap(), all.quantile(q).unwrap());
+            assert!(
+                (merged - sequential).abs() < 50.0,
+                "q {} {} {}",
+                q,
+                merged,
+                sequential
+            );
+        }
+    }
+
+    #[test]
+    fn test_large_uniform_input() {
+        let mut digest = TDigest::new();
+        for value in 0..100_000 {
+            digest.add(value as f64);
+        }
+        assert_eq!(digest.count(), 100_000.0);
+        assert!(digest.centroids.len() < 1_000);
+        for q in [0.01, 0.25, 0.5, 0.75, 0.99] {
+            let estimate = digest.quantile(q).unwrap();
+            let expected = q * 100_000.0;
+            assert!(
+                (estimate - expected).abs() < 500.0,
+                "q {} estimate {} expected {}",
+                q,
+                estimate,
+                expected
+            );
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use crate::date_stats::DateParser;
+use chrono::NaiveDateTime;
+use std::str::FromStr;
+
+/// Unit timestamps are truncated to
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TimeUnit {
+    Hour,
+    Day,
+    /// ISO week, starting on Monday
+    Week,
+    Month,
+}
+
+impl TimeUnit {
+    /// Label of the bucket of the datetime, the labels sort chronologically
+    pub fn bucket(&self, datetime: NaiveDateTime) -> String {
+        let format = match self {
+            Self::Hour => "%Y-%m-%dT%H:00",
+            Self::Day => "%Y-%m-%d",
+            Self::Week => "%G-W%V",
+            Self::Month => "%Y-%m",
+        };
+        datetime.format(format).to_string()
+    }
+}
+
+impl FromStr for TimeUnit {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "hour" => Ok(Self::Hour),
+            "day" => Ok(Self::Day),
+            "week" => Ok(Self::Week),
+            "month" => Ok(Self::Month),
+            _ => Err(format!("{} is not one of hour, day, week or month", value)),
+        }
+    }
+}
+
+/// Timestamp column (header name or 1-based index) whose truncated values are a group key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimeBucket {
+    pub column: String,
+    pub unit: TimeUnit,
+}
+
+impl FromStr for TimeBucket {
+    type Err = String;
+
+    /// Parse `<column>=<hour|day|week|month>`
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (column, unit) = value
+            .rsplit_once('=')
+            .ok_or_else(|| format!("{} is not like <column>=<hour|day|week|month>", value))?;
+        Ok(Self {
+            column: column.to_string(),
+            unit: unit.parse()?,
+        })
+    }
+}
+
+/// Time buckets resolved to positions in the group keys
+pub(crate) struct GroupBuckets {
+    buckets: Vec<(usize, TimeUnit)>,
+    date_parser: DateParser,
+}
+
+impl GroupBuckets {
+    pub(crate) fn new(buckets: Vec<(usize, TimeUnit)>, date_parser: DateParser) -> Self {
+        Self {
+            buckets,
+            date_parser,
+        }
+    }
+
+    /// Positions of the time buckets in the group keys
+    pub(crate) fn positions(&self) -> Vec<usize> {
+        self.buckets
+            .iter()
+            .map(|&(position, _unit)| position)
+            .collect()
+    }
+
+    /// Replace the timestamps of the group key by their bucket, `<INVALID>` when not a date
+    pub(crate) fn apply(&self, group: &mut [String]) {
+        for &(position, unit) in &self.buckets {
+            if let Some(value) = group.get_mut(position).filter(|value| !value.is_empty()) {
+                *value = match self.date_parser.parse(value) {
+                    Some((datetime, _format)) => unit.bucket(datetime),
+                    None => "<INVALID>".to_string(),
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket() {
+        let datetime =
+            NaiveDateTime::parse_from_str("2024-01-01 10:30:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        assert_eq!(TimeUnit::Hour.bucket(datetime), "2024-01-01T10:00");
+        assert_eq!(TimeUnit::Day.bucket(datetime), "2024-01-01");
+        assert_eq!(TimeUnit::Week.bucket(datetime), "2024-W01");
+        assert_eq!(TimeUnit::Month.bucket(datetime), "2024-01");
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            "at=day".parse(),
+            Ok(TimeBucket {
+                column: "at".to_string(),
+                unit: TimeUnit::Day
+            })
+        );
+        assert!("at".parse::<TimeBucket>().is_err());
+        assert!("at=year".parse::<TimeBucket>().is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        let buckets = GroupBuckets::new(vec![(1, TimeUnit::Month)], DateParser::default());
+        let mut group = vec!["2024-03-01".to_string(), "2024-03-01T10:00:00Z".to_string()];
+        buckets.apply(&mut group);
+        assert_eq!(group, ["2024-03-01", "2024-03"]);
+        let mut group = vec!["a".to_string(), "x".to_string()];
+        buckets.apply(&mut group);
+        assert_eq!(group, ["a", "<INVALID>"]);
+    }
+}
@@ -0,0 +1,196 @@
+use crate::date_stats::DateParser;
+use serde::Serialize;
+use std::fmt;
+
+/// Types inferred for values, from the most specific to the most general
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueType {
+    Integer,
+    Float,
+    Boolean,
+    Date,
+    Datetime,
+    String,
+}
+
+impl ValueType {
+    /// Most specific type of a (non-empty) value
+    pub fn of(value: &str) -> Self {
+        let is_number = value.parse::<f64>().is_ok();
+        let date_parser = DateParser::new(&[], false);
+        let date_format = (!is_number)
+            .then(|| date_parser.parse(value))
+            .flatten()
+            .map(|(_, format)| format);
+        Self::parsed(value, is_number, date_format)
+    }
+
+    /// Most specific type of a (non-empty) value from its parse results, whether it is a number
+    /// and the format it parsed as a date in, so that it is not parsed again
+    pub fn parsed(value: &str, is_number: bool, date_format: Option<&str>) -> Self {
+        if is_number {
+            if value.parse::<i64>().is_ok() {
+                Self::Integer
+            } else {
+                Self::Float
+            }
+        } else if value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false") {
+            Self::Boolean
+        } else {
+            match date_format {
+                Some("iso8601-date") => Self::Date,
+                Some("rfc3339" | "iso8601-datetime") => Self::Datetime,
+                _ => Self::String,
+            }
+        }
+    }
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Integer => "integer",
+            Self::Float => "float",
+            Self::Boolean => "boolean",
+            Self::Date => "date",
+            Self::Datetime => "datetime",
+            Self::String => "string",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Counts of the value types, to infer the type of a column
+#[derive(Clone, Debug, Default)]
+pub struct TypeStats {
+    count: usize,
+    integer_count: usize,
+    float_count: usize,
+    boolean_count: usize,
+    date_count: usize,
+    datetime_count: usize,
+}
+
+impl TypeStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn add(&mut self, value: &str) {
+        self.add_type(ValueType::of(value));
+    }
+    /// Add a value of a known type
+    pub fn add_type(&mut self, value_type: ValueType) {
+        self.count += 1;
+        match value_type {
+            ValueType::Integer => self.integer_count += 1,
+            ValueType::Float => self.float_count += 1,
+            ValueType::Boolean => self.boolean_count += 1,
+            ValueType::Date => self.date_count += 1,
+            ValueType::Datetime => self.datetime_count += 1,
+            ValueType::String => {}
+        }
+    }
+    /// Combine with stats of another part of the input, as if all values were added to this one
+    pub fn merge(&mut self, other: &Self) {
+        self.count += other.count;
+        self.integer_count += other.integer_count;
+        self.float_count += other.float_count;
+        self.boolean_count += other.boolean_count;
+        self.date_count += other.date_count;
+        self.datetime_count += other.datetime_count;
+    }
+    pub fn count(&self) -> usize {
+        self.count
+    }
+    /// Number of values conforming to the type (integers conform to float, all to string)
+    pub fn conforming(&self, value_type: ValueType) -> usize {
+        match value_type {
+            ValueType::Integer => self.integer_count,
+            ValueType::Float => self.integer_count + self.float_count,
+            ValueType::Boolean => self.boolean_count,
+            ValueType::Date => self.date_count,
+            ValueType::Datetime => self.datetime_count,
+            ValueType::String => self.count,
+        }
+    }
+    /// Most specific type with the most conforming values, string unless it covers at least half
+    /// of the values
+    pub fn inferred_type(&self) -> ValueType {
+        [
+            ValueType::Integer,
+            ValueType::Float,
+            ValueType::Boolean,
+            ValueType::Date,
+            ValueType::Datetime,
+        ]
+        .into_iter()
+        .rev()
+        .max_by_key(|&value_type| self.conforming(value_type))
+        .filter(|&value_type| self.count > 0 && self.conforming(value_type) * 2 >= self.count)
+        .unwrap_or(ValueType::String)
+    }
+    /// Share of the values conforming to the inferred type (0 without values)
+    pub fn share(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        self.conforming(self.inferred_type()) as f64 / self.count as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_of() {
+        assert_eq!(ValueType::of("-12"), ValueType::Integer);
+        assert_eq!(ValueType::of("1.5e3"), ValueType::Float);
+        assert_eq!(ValueType::of("TRUE"), ValueType::Boolean);
+        assert_eq!(ValueType::of("2024-02-29"), ValueType::Date);
+        assert_eq!(ValueType::of("2024-02-30"), ValueType::String);
+        assert_eq!(ValueType::of("2024-02-29T12:30:00Z"), ValueType::Datetime);
+        assert_eq!(ValueType::of("2024-02-29 12:30:00.5"), ValueType::Datetime);
+        assert_eq!(ValueType::of("abc"), ValueType::String);
+        assert_eq!(
+            ValueType::parsed("2024-02-29", false, Some("iso8601-date")),
+            ValueType::Date
+        );
+        assert_eq!(
+            ValueType::parsed("29/02/2024", false, Some("%d/%m/%Y")),
+            ValueType::String
+        );
+    }
+
+    #[test]
+    fn test_inferred_type() {
+        let mut stats = TypeStats::new();
+        assert_eq!(stats.inferred_type(), ValueType::String);
+        assert_eq!(stats.share(), 0.0);
+
+        for value in ["1", "2", "3", "x"] {
+            stats.add(value);
+        }
+        assert_eq!(stats.inferred_type(), ValueType::Integer);
+        assert_eq!(stats.share(), 0.75);
+
+        stats.add("2.5");
+        assert_eq!(stats.inferred_type(), ValueType::Float);
+        assert_eq!(stats.share(), 0.8);
+
+        let mut other = TypeStats::new();
+        for value in ["a", "b", "c"] {
+            other.add(value);
+        }
+        stats.merge(&other);
+        assert_eq!(stats.count(), 8);
+        assert_eq!(stats.inferred_type(), ValueType::Float);
+        assert_eq!(stats.share(), 0.5);
+
+        other.add("d");
+        stats.merge(&other);
+        assert_eq!(stats.inferred_type(), ValueType::String);
+        assert_eq!(stats.share(), 1.0);
+    }
+}
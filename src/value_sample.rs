@@ -0,0 +1,147 @@
+/// Bounded sample of values: the first `size` values and a uniform reservoir of `size` of the
+/// following ones (algorithm R, with a deterministic pseudo random generator)
+#[derive(Clone, Debug)]
+pub struct ValueSample<T = String> {
+    size: usize,
+    first_size: usize,
+    count: usize,
+    first: Vec<T>,
+    reservoir: Vec<T>,
+    state: u64,
+}
+
+impl<T: Clone> ValueSample<T> {
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            first_size: size,
+            count: 0,
+            first: Vec::new(),
+            reservoir: Vec::new(),
+            state: 0x9e37_79b9_7f4a_7c15,
+        }
+    }
+    /// Only a uniform reservoir of `size` values
+    pub fn uniform(size: usize) -> Self {
+        Self {
+            first_size: 0,
+            ..Self::new(size)
+        }
+    }
+    pub fn add(&mut self, value: T) {
+        self.count += 1;
+        if self.first.len() < self.first_size {
+            self.first.push(value);
+        } else if self.reservoir.len() < self.size {
+            self.reservoir.push(value);
+        } else {
+            let index = self.random(self.count - self.first.len());
+            if index < self.size {
+                self.reservoir[index] = value;
+            }
+        }
+    }
+    /// Combine with a sample of the following part of the input
+    pub fn merge(&mut self, other: &Self) {
+        if self.size == 0 {
+            self.size = other.size;
+            self.first_size = other.first_size;
+        }
+        let population = self.count - self.first.len();
+        let mut other_values = other.first.iter().chain(&other.reservoir).cloned();
+        while self.first.len() < self.first_size {
+            match other_values.next() {
+                Some(value) => self.first.push(value),
+                None => break,
+            }
+        }
+        let mut other_pool: Vec<T> = other_values.collect();
+        let mut other_population = (self.count + other.count)
+            .saturating_sub(self.first.len())
+            .saturating_sub(population);
+        self.count += other.count;
+
+        // Draw each value of the merged reservoir from either side, weighted by the number of
+        // values each side stands for
+        let mut pool = std::mem::take(&mut self.reservoir);
+        let mut population = population;
+        while self.reservoir.len() < self.size && !(pool.is_empty() && other_pool.is_empty()) {
+            let from_self = other_pool.is_empty()
+                || (!pool.is_empty() && self.random(population + other_population) < population);
+            let (values, population) = if from_self {
+                (&mut pool, &mut population)
+            } else {
+                (&mut other_pool, &mut other_population)
+            };
+            let index = self.random(values.len());
+            self.reservoir.push(values.swap_remove(index));
+            *population = population.saturating_sub(1);
+        }
+    }
+    /// The first values followed by the reservoir
+    pub fn values(&self) -> Vec<T> {
+        self.first.iter().chain(&self.reservoir).cloned().collect()
+    }
+
+    /// Pseudo random number below `bound` (splitmix64)
+    fn random(&mut self, bound: usize) -> usize {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        (z % bound.max(1) as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add() {
+        let mut sample = ValueSample::new(2);
+        assert!(sample.values().is_empty());
+        for value in 0..1000 {
+            sample.add(value.to_string());
+        }
+        let values = sample.values();
+        assert_eq!(values.len(), 4);
+        assert_eq!(values[..2], ["0", "1"]);
+        assert!(values[2..]
+            .iter()
+            .all(|value| value.parse::<usize>().unwrap() >= 2));
+    }
+
+    #[test]
+    fn test_merge() {
+        let (mut left, mut right) = (ValueSample::new(3), ValueSample::new(3));
+        left.add("a".to_string());
+        for value in ["b", "c", "d", "e", "f", "g", "h", "i"] {
+            right.add(value.to_string());
+        }
+        left.merge(&right);
+        let values = left.values();
+        assert_eq!(values.len(), 6);
+        assert_eq!(values[..3], ["a", "b", "c"]);
+        assert!(values[3..].iter().all(|value| value.as_str() > "c"));
+
+        let mut empty = ValueSample::new(0);
+        empty.merge(&left);
+        assert_eq!(empty.values().len(), 6);
+    }
+
+    #[test]
+    fn test_uniform() {
+        let (mut left, mut right) = (ValueSample::uniform(10), ValueSample::uniform(10));
+        for value in 0..500 {
+            left.add(value);
+            right.add(value + 500);
+        }
+        left.merge(&right);
+        let values = left.values();
+        assert_eq!(values.len(), 10);
+        // Not only the first values
+        assert!(values.iter().any(|&value| value >= 10));
+    }
+}